@@ -22,12 +22,38 @@ pub const MAX_ELEVATOR_DEPTH: i32 = MAP_HEIGHT - SKY_HEIGHT - 2;
 pub const ELEVATOR_SHAFT_X: i32 = MAP_WIDTH - 3;
 pub const SKY_HEIGHT: i32 = 3;
 pub const GRASS_LEVEL: i32 = SKY_HEIGHT + 1;
+pub const BANK_X: i32 = MAP_WIDTH - 9;
+// Tunnels deeper than this (below the grass) only get air from an elevator shaft.
+pub const VENTILATED_DEPTH: i32 = 20;
 
-pub const PLAYER_LAYER: i32 = 1;
-pub const ELEVATOR_LAYER: i32 = 2;
+// Players move, dig and fall in ticks of this many seconds.
+pub const PLAYER_TICK: f64 = 0.1;
+// And the elevators move in these.
+pub const ELEVATOR_TICK: f64 = 0.05;
+
+pub const RESCUE_COST: i32 = 250;
+// Putting up a button to call the elevator to another level.
+pub const CALL_BUTTON_COST: i32 = 100;
+// Putting an elevator down a tunnel.
+pub const SHAFT_COST: i32 = 3000;
+// Seconds in a game day.
+pub const DAY_LENGTH: f64 = 120.0;
+
+// How far from where they start that floods and cave-ins reach.
+pub const HAZARD_RANGE: i32 = 5;
+pub const CAVE_IN_DAMAGE: i32 = 25;
+// Health lost every half second without air.
+pub const SUFFOCATION_DAMAGE: i32 = 10;
 
-pub const TREASURE_BASE_VALUE: i32 = 200;
+// Lifetime statistics and high scores are kept in the working directory.
+pub const STATS_FILE: &str = "miner_stats.txt";
+pub const SCORES_FILE: &str = "miner_scores.txt";
 
+pub const DYNAMITE_LAYER: i32 = 1;
+pub const ELEVATOR_LAYER: i32 = 2;
+pub const CURSOR_LAYER: i32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SpriteIndex {
     Empty = 0,
     Dirt,
@@ -41,17 +67,31 @@ pub enum SpriteIndex {
 
     Person = 10,
     Ladder,
+    Coal,
+    Copper,
+    Diamond,
+    Pump,
+    Cursor,
 
-    Treasure0 = 17,
+    Silver = 17,
+    Gold,
+    Ruby,
 
     BankTopLeft = 23,
     BankTopRight,
     BankBottomLeft = 33,
     BankBottomRight,
 
+    PersonWalk = 28,
+    PersonDig = 35,
+    PersonClimb = 37,
+    PersonFall = 39,
+
     Elevator = 30,
     ElevatorHook = 20,
     ElevatorTowerTop = 21,
+    Dynamite = 22,
     ElevatorTowerBottom = 31,
     ElevatorCable = 32,
+    CallButton = 26,
 }
@@ -21,30 +21,93 @@ mod constants;
 use constants::*;
 
 mod model;
-use model::elevator::Elevator;
-use model::player::Player;
+use model::dynamite::Charges;
+use model::key_repeat::RepeatSettings;
+use model::path::AutoWalk;
+use model::pump::Pumps;
+use model::run::Run;
+use model::score::Score;
+use model::shop::Shop;
 
 mod systems;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let name = std::env::var("USER")
+        .ok()
+        .filter(|name| Score::is_valid_name(name))
+        .unwrap_or_else(|| "Miner".to_string());
+    let (repeat, run) = match RepeatSettings::from_args(&args)
+        .and_then(|(repeat, args)| Ok((repeat, Run::from_args(&args, &name)?)))
+    {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!(
+                "Usage: miner [--name NAME] [--difficulty easy|normal|hard] [--seed N]\n             \
+                 [--repeat-delay MILLISECONDS] [--repeat-rate PER_SECOND]"
+            );
+            std::process::exit(2);
+        }
+    };
+
     App::new()
         // Disable MSAA, as it produces weird rendering artifacts
         .insert_resource(Msaa { samples: 1 })
-        .insert_resource(Elevator::new(MAX_ELEVATOR_DEPTH))
-        .insert_resource(Player::new(PLAYER_START_X, PLAYER_START_Y))
+        .insert_resource(run)
+        .insert_resource(repeat)
+        .insert_resource(Pumps::default())
+        .insert_resource(Charges::default())
+        .insert_resource(AutoWalk::default())
+        .insert_resource(Shop::default())
+        .insert_resource(systems::input::HoveredTile::default())
+        .insert_resource(systems::input::Screen::default())
         .add_plugins(DefaultPlugins)
         .add_plugin(SimpleTileMapPlugin)
         .add_startup_system(systems::render::setup)
         .add_startup_system(systems::text::setup)
+        .add_startup_system(systems::player::setup)
+        .add_startup_system(systems::elevator::setup)
+        .add_startup_system(systems::stats::setup)
+        .add_startup_system(systems::scores::setup)
         .add_system(systems::input::camera_input)
         .add_system(systems::input::elevator_input)
         .add_system(systems::input::player_input)
-        .add_system(systems::player::move_player)
-        .add_system(systems::elevator::move_elevator.with_run_criteria(FixedTimestep::step(0.1)))
+        .add_system(systems::input::mouse_input)
+        .add_system(systems::input::shop_input)
+        .add_system(systems::input::screen_input)
+        .add_system(
+            systems::player::move_player.with_run_criteria(FixedTimestep::step(PLAYER_TICK)),
+        )
+        .add_system(systems::player::burn_fuses)
+        .add_system(systems::player::check_health)
+        .add_system(systems::scores::count_time)
+        .add_system(systems::scores::end_run)
+        .add_system(systems::player::auto_walk.with_run_criteria(FixedTimestep::step(0.15)))
+        .add_system(
+            systems::elevator::move_elevator.with_run_criteria(FixedTimestep::step(ELEVATOR_TICK)),
+        )
+        .add_system(systems::player::breathe.with_run_criteria(FixedTimestep::step(0.5)))
+        .add_system(
+            systems::player::charge_interest.with_run_criteria(FixedTimestep::step(DAY_LENGTH)),
+        )
+        .add_system(systems::player::run_pumps.with_run_criteria(FixedTimestep::step(1.0)))
+        .add_system(systems::stats::save_stats.with_run_criteria(FixedTimestep::step(5.0)))
         .add_system(systems::render::update_tilemap)
+        .add_system(systems::render::add_sprites)
+        .add_system(systems::render::animate)
         .add_system(systems::render::show_player)
         .add_system(systems::render::show_elevator)
+        .add_system(systems::render::show_dynamite)
+        .add_system(systems::render::show_cursor)
         .add_system(systems::text::update_money)
         .add_system(systems::text::update_energy)
+        .add_system(systems::text::update_bars)
+        .add_system(systems::text::update_oxygen)
+        .add_system(systems::text::update_pumps)
+        .add_system(systems::text::update_load)
+        .add_system(systems::text::update_pick)
+        .add_system(systems::text::update_shop)
+        .add_system(systems::text::update_screen)
         .run();
 }
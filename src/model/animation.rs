@@ -0,0 +1,189 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::position::Position;
+use bevy::prelude::Component;
+
+// How fast sprites slide from one tile to the next, in tiles a second. It's the
+// elevator's speed, so riders keep up with the car.
+pub const SLIDE_SPEED: f32 = 10.0;
+// Anything further behind than this was teleported, and just jumps there.
+const SNAP_DISTANCE: f32 = 3.0;
+// How long each frame of a two-frame cycle is shown.
+const FRAME_TIME: f32 = 0.15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pose {
+    Stand,
+    Walk,
+    Dig,
+    Climb,
+    Fall,
+}
+
+impl Pose {
+    // What a step from one tile to another looks like, if it was anything to see.
+    pub fn of_step(from: Position, to: Position, falling: bool, dug: bool) -> Option<Pose> {
+        if dug {
+            Some(Pose::Dig)
+        } else if from == to {
+            None
+        } else if falling {
+            Some(Pose::Fall)
+        } else if from.y != to.y {
+            Some(Pose::Climb)
+        } else {
+            Some(Pose::Walk)
+        }
+    }
+}
+
+// Where a sprite is drawn. It trails the position on the grid, so moves from one
+// tile to the next are smooth.
+#[derive(Component)]
+pub struct Animation {
+    pub x: f32,
+    pub y: f32,
+    pub pose: Pose,
+    pub facing_left: bool,
+    // Time spent moving, which drives the frame cycles.
+    time: f32,
+}
+
+impl Animation {
+    pub fn new(pos: Position) -> Self {
+        Animation {
+            x: pos.x as f32,
+            y: pos.y as f32,
+            pose: Pose::Stand,
+            facing_left: false,
+            time: 0.0,
+        }
+    }
+
+    // Starts showing a new step, dx tiles sideways.
+    pub fn start(&mut self, pose: Pose, dx: i32) {
+        self.pose = pose;
+        if dx != 0 {
+            self.facing_left = dx < 0;
+        }
+    }
+
+    pub fn is_at(&self, pos: Position) -> bool {
+        (self.x, self.y) == (pos.x as f32, pos.y as f32)
+    }
+
+    // Slides towards pos. Once there, settles into the resting pose.
+    pub fn update(&mut self, pos: Position, rest: Pose, seconds: f32) {
+        let (dx, dy) = (pos.x as f32 - self.x, pos.y as f32 - self.y);
+        if f32::max(dx.abs(), dy.abs()) > SNAP_DISTANCE {
+            self.x = pos.x as f32;
+            self.y = pos.y as f32;
+        } else {
+            let step = SLIDE_SPEED * seconds;
+            self.x += dx.clamp(-step, step);
+            self.y += dy.clamp(-step, step);
+        }
+        if self.is_at(pos) {
+            self.pose = rest;
+        } else {
+            self.time += seconds;
+        }
+    }
+
+    // The sprite to show, and whether to flip it.
+    pub fn sprite(&self) -> (SpriteIndex, bool) {
+        let second_frame = (self.time / FRAME_TIME) as i32 % 2 == 1;
+        match self.pose {
+            Pose::Stand => (SpriteIndex::Person, self.facing_left),
+            Pose::Walk if second_frame => (SpriteIndex::PersonWalk, self.facing_left),
+            Pose::Walk => (SpriteIndex::Person, self.facing_left),
+            Pose::Dig => (SpriteIndex::PersonDig, self.facing_left),
+            // Hand over hand.
+            Pose::Climb => (SpriteIndex::PersonClimb, second_frame),
+            Pose::Fall => (SpriteIndex::PersonFall, self.facing_left),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poses() {
+        let p = Position::new(5, 5);
+        assert_eq!(None, Pose::of_step(p, p, false, false));
+        assert_eq!(Some(Pose::Dig), Pose::of_step(p, p, false, true));
+        let right = Position::new(6, 5);
+        assert_eq!(Some(Pose::Walk), Pose::of_step(p, right, false, false));
+        assert_eq!(Some(Pose::Dig), Pose::of_step(p, right, false, true));
+        let up = Position::new(5, 4);
+        assert_eq!(Some(Pose::Climb), Pose::of_step(p, up, false, false));
+        let down = Position::new(5, 6);
+        assert_eq!(Some(Pose::Climb), Pose::of_step(p, down, false, false));
+        assert_eq!(Some(Pose::Fall), Pose::of_step(p, down, true, false));
+    }
+
+    #[test]
+    fn sliding() {
+        let mut a = Animation::new(Position::new(5, 5));
+        let next = Position::new(6, 5);
+        a.start(Pose::Walk, 1);
+        a.update(next, Pose::Stand, 0.05);
+        assert_eq!((5.5, 5.0), (a.x, a.y));
+        assert_eq!(Pose::Walk, a.pose);
+        assert!(!a.is_at(next));
+        // It doesn't overshoot, and settles down once there.
+        a.update(next, Pose::Stand, 1.0);
+        assert!(a.is_at(next));
+        assert_eq!(Pose::Stand, a.pose);
+    }
+
+    #[test]
+    fn snapping() {
+        let mut a = Animation::new(Position::new(5, 5));
+        let far = Position::new(5, 30);
+        a.update(far, Pose::Stand, 0.01);
+        assert!(a.is_at(far));
+    }
+
+    #[test]
+    fn facing() {
+        let mut a = Animation::new(Position::new(5, 5));
+        assert!(!a.facing_left);
+        a.start(Pose::Walk, -1);
+        assert!(a.facing_left);
+        // Climbing doesn't turn around.
+        a.start(Pose::Climb, 0);
+        assert!(a.facing_left);
+        a.start(Pose::Fall, 0);
+        assert_eq!((SpriteIndex::PersonFall, true), a.sprite());
+    }
+
+    #[test]
+    fn walk_cycle() {
+        let mut a = Animation::new(Position::new(5, 5));
+        a.start(Pose::Walk, 1);
+        assert_eq!(SpriteIndex::Person, a.sprite().0);
+        let far = Position::new(8, 5);
+        a.update(far, Pose::Stand, FRAME_TIME * 0.75);
+        assert_eq!(SpriteIndex::Person, a.sprite().0);
+        a.update(far, Pose::Stand, FRAME_TIME * 0.75);
+        assert_eq!(SpriteIndex::PersonWalk, a.sprite().0);
+    }
+}
@@ -0,0 +1,125 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+pub const CREDIT_LIMIT: i32 = 2000;
+// How much the bank lends or takes back at a time.
+pub const LOAN_STEP: i32 = 100;
+// Charged on the debt once a day.
+pub const INTEREST_PERCENT: i32 = 5;
+
+// Money owed to the bank.
+pub struct Loan {
+    debt: i32,
+    limit: i32,
+}
+
+impl Loan {
+    pub fn new(limit: i32) -> Self {
+        Loan { debt: 0, limit }
+    }
+
+    pub fn debt(&self) -> i32 {
+        self.debt
+    }
+
+    pub fn limit(&self) -> i32 {
+        self.limit
+    }
+
+    // How much more the bank is willing to lend.
+    pub fn available(&self) -> i32 {
+        core::cmp::max(0, self.limit - self.debt)
+    }
+
+    pub fn borrow(&mut self, amount: i32) -> bool {
+        let ok = amount <= self.available();
+        if ok {
+            self.debt += amount;
+        }
+        ok
+    }
+
+    // Pays back up to the given amount, returning how much was actually owed.
+    pub fn repay(&mut self, amount: i32) -> i32 {
+        let paid = core::cmp::min(amount, self.debt);
+        self.debt -= paid;
+        paid
+    }
+
+    // Adds a day's interest to the debt, rounded up, and returns it.
+    pub fn charge_interest(&mut self) -> i32 {
+        let interest = (self.debt * INTEREST_PERCENT + 99) / 100;
+        self.debt += interest;
+        interest
+    }
+
+    // Interest can push the debt past the limit, and then the bank wants it all back.
+    pub fn is_over_limit(&self) -> bool {
+        self.debt > self.limit
+    }
+}
+
+impl Default for Loan {
+    fn default() -> Self {
+        Loan::new(CREDIT_LIMIT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrow_and_repay() {
+        let mut l = Loan::new(1000);
+        assert_eq!(0, l.debt());
+        assert!(l.borrow(600));
+        assert_eq!(400, l.available());
+        assert!(!l.borrow(401));
+        assert!(l.borrow(400));
+        assert_eq!(0, l.available());
+
+        assert_eq!(300, l.repay(300));
+        assert_eq!(700, l.debt());
+        assert_eq!(700, l.repay(1000));
+        assert_eq!(0, l.debt());
+        assert_eq!(0, l.repay(1));
+    }
+
+    #[test]
+    fn interest() {
+        let mut l = Loan::new(1000);
+        assert_eq!(0, l.charge_interest());
+        l.borrow(100);
+        assert_eq!(100 * INTEREST_PERCENT / 100, l.charge_interest());
+        // Rounded up.
+        l.repay(l.debt());
+        l.borrow(1);
+        assert_eq!(1, l.charge_interest());
+        assert_eq!(2, l.debt());
+    }
+
+    #[test]
+    fn over_the_limit() {
+        let mut l = Loan::new(1000);
+        l.borrow(1000);
+        assert!(!l.is_over_limit());
+        l.charge_interest();
+        assert!(l.is_over_limit());
+        assert_eq!(0, l.available());
+        assert!(!l.borrow(1));
+    }
+}
@@ -0,0 +1,238 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::map::{Map, TileType};
+
+// Seconds from lighting the fuse to the bang.
+const FUSE_TIME: f32 = 3.0;
+const BLAST_RADIUS: i32 = 2;
+// Damage to someone standing right on top of the charge. It drops off with distance.
+const BLAST_DAMAGE: i32 = 60;
+
+// Whether a blast clears this tile. Treasure is tough enough to survive, and the
+// border is bedrock.
+fn is_blastable(t: TileType) -> bool {
+    matches!(
+        t,
+        TileType::Dirt | TileType::Grass | TileType::Ladder | TileType::Rock { .. }
+    )
+}
+
+pub struct Dynamite {
+    pub x: i32,
+    pub y: i32,
+    fuse: f32,
+}
+
+impl Dynamite {
+    pub fn new(x: i32, y: i32) -> Self {
+        Dynamite {
+            x,
+            y,
+            fuse: FUSE_TIME,
+        }
+    }
+
+    // The tiles the blast clears. It can't reach past the border, and leaves the
+    // elevator shafts alone.
+    pub fn blast_area(&self, map: &Map) -> Vec<(i32, i32)> {
+        map.radius(self.x, self.y, BLAST_RADIUS)
+            .filter(|&(x, y)| {
+                x < ELEVATOR_SHAFT_X
+                    && !map.is_shaft(x)
+                    && is_blastable(map.tile(x, y))
+                    && map.line_of_sight((self.x, self.y), (x, y), |t| t != TileType::Border)
+            })
+            .collect()
+    }
+
+    // Sets the charge off, returning the tiles that were cleared.
+    pub fn explode(&self, map: &mut Map) -> Vec<(i32, i32)> {
+        let area = self.blast_area(map);
+        for &(x, y) in &area {
+            map.set_tile(x, y, TileType::Empty);
+        }
+        area
+    }
+
+    // How badly the blast hurts someone standing at (x, y).
+    pub fn damage(&self, map: &Map, x: i32, y: i32) -> i32 {
+        let d2 = (x - self.x) * (x - self.x) + (y - self.y) * (y - self.y);
+        let reach = BLAST_RADIUS + 1;
+        if d2 > reach * reach
+            || !map.line_of_sight((self.x, self.y), (x, y), |t| t != TileType::Border)
+        {
+            return 0;
+        }
+        BLAST_DAMAGE / (1 + d2)
+    }
+}
+
+// All of the charges with lit fuses.
+#[derive(Default)]
+pub struct Charges {
+    charges: Vec<Dynamite>,
+}
+
+impl Charges {
+    // Lights a charge, unless there is one there already.
+    pub fn add(&mut self, dynamite: Dynamite) -> bool {
+        let ok = !self
+            .charges
+            .iter()
+            .any(|d| (d.x, d.y) == (dynamite.x, dynamite.y));
+        if ok {
+            self.charges.push(dynamite);
+        }
+        ok
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Dynamite> {
+        self.charges.iter()
+    }
+
+    // Burns the fuses down, returning the charges that have gone off.
+    pub fn tick(&mut self, seconds: f32) -> Vec<Dynamite> {
+        for d in self.charges.iter_mut() {
+            d.fuse -= seconds;
+        }
+        let (done, lit) = self.charges.drain(..).partition(|d| d.fuse <= 0.0);
+        self.charges = lit;
+        done
+    }
+
+    // Charges caught in a blast go off right away.
+    pub fn set_off(&mut self, area: &[(i32, i32)]) {
+        for d in self.charges.iter_mut() {
+            if area.contains(&(d.x, d.y)) {
+                d.fuse = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rock all around (10, 20).
+    fn rock_map() -> Map {
+        let mut m = Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
+        for (x, y) in m.rect(5, 15, 15, 25) {
+            m.set_tile(x, y, TileType::Rock { hardness: 3 });
+        }
+        m.set_tile(10, 20, TileType::Empty);
+        m
+    }
+
+    fn sorted(mut v: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+        v.sort_unstable();
+        v
+    }
+
+    #[test]
+    fn blast_shape() {
+        let mut m = rock_map();
+        let d = Dynamite::new(10, 20);
+        let area = d.explode(&mut m);
+        // A disk of radius 2, less the empty tile in the middle.
+        assert_eq!(12, area.len());
+        assert_eq!(TileType::Empty, m.tile(12, 20));
+        assert_eq!(TileType::Empty, m.tile(11, 21));
+        assert_eq!(TileType::Rock { hardness: 3 }, m.tile(12, 21));
+        assert_eq!(TileType::Rock { hardness: 3 }, m.tile(13, 20));
+        assert!(d.blast_area(&m).is_empty());
+    }
+
+    #[test]
+    fn treasure_and_water_survive() {
+        let mut m = rock_map();
+        m.set_tile(10, 21, TileType::Water);
+        m.set_tile(
+            9,
+            20,
+            TileType::Treasure {
+                mineral: crate::model::mineral::Mineral::Gold,
+            },
+        );
+        let area = Dynamite::new(10, 20).blast_area(&m);
+        assert_eq!(10, area.len());
+        assert!(!area.contains(&(10, 21)));
+        assert!(!area.contains(&(9, 20)));
+    }
+
+    #[test]
+    fn spares_border_and_shaft() {
+        let m = Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
+        // Next to the left edge.
+        let area = Dynamite::new(1, 20).blast_area(&m);
+        assert!(area.iter().all(|&(x, _)| x >= 1));
+        assert!(area.contains(&(3, 20)));
+        // Next to the elevator shaft.
+        let area = Dynamite::new(ELEVATOR_SHAFT_X - 1, 20).blast_area(&m);
+        assert!(area.iter().all(|&(x, _)| x < ELEVATOR_SHAFT_X));
+        // At the bottom of the mine.
+        let area = Dynamite::new(10, MAP_HEIGHT - 2).blast_area(&m);
+        assert!(area.iter().all(|&(_, y)| y < MAP_HEIGHT - 1));
+        assert_eq!(
+            sorted(vec![
+                (8, MAP_HEIGHT - 2),
+                (9, MAP_HEIGHT - 3),
+                (9, MAP_HEIGHT - 2),
+                (10, MAP_HEIGHT - 4),
+                (10, MAP_HEIGHT - 3),
+                (10, MAP_HEIGHT - 2),
+                (11, MAP_HEIGHT - 3),
+                (11, MAP_HEIGHT - 2),
+                (12, MAP_HEIGHT - 2),
+            ]),
+            sorted(area)
+        );
+    }
+
+    #[test]
+    fn damage_falls_off() {
+        let m = rock_map();
+        let d = Dynamite::new(10, 20);
+        assert_eq!(BLAST_DAMAGE, d.damage(&m, 10, 20));
+        assert_eq!(BLAST_DAMAGE / 2, d.damage(&m, 11, 20));
+        assert!(d.damage(&m, 13, 20) > 0);
+        assert_eq!(0, d.damage(&m, 14, 20));
+        assert_eq!(0, d.damage(&m, 12, 23));
+    }
+
+    #[test]
+    fn fuses() {
+        let mut c = Charges::default();
+        assert!(c.add(Dynamite::new(10, 20)));
+        assert!(!c.add(Dynamite::new(10, 20)));
+        assert!(c.tick(FUSE_TIME / 2.0).is_empty());
+        assert!(c.add(Dynamite::new(12, 20)));
+
+        let done = c.tick(FUSE_TIME / 2.0);
+        assert_eq!(
+            vec![(10, 20)],
+            done.iter().map(|d| (d.x, d.y)).collect::<Vec<_>>()
+        );
+        assert_eq!(1, c.iter().count());
+
+        // Caught in the blast.
+        c.set_off(&[(12, 20)]);
+        assert_eq!(1, c.tick(0.0).len());
+        assert_eq!(0, c.iter().count());
+    }
+}
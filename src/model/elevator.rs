@@ -14,88 +14,752 @@
 
 #![warn(clippy::all)]
 
+use bevy::prelude::Component;
+use rand::Rng;
+use std::collections::BTreeSet;
+
+// Rows a second gained or lost every second.
+const ACCELERATION: f32 = 12.0;
+// Top speeds in rows a second, for each motor the bank sells.
+const TOP_SPEEDS: [f32; 3] = [5.0, 7.5, 10.0];
+// How much each size of car carries at full speed. Any more and it crawls.
+const CAPACITIES: [i32; 2] = [100, 200];
+// What a rider weighs before counting what they carry.
+pub const RIDER_WEIGHT: i32 = 20;
+// Seconds the doors stay open at each stop.
+const DOOR_TIME: f32 = 1.0;
+// The power for each row travelled, for each motor. Faster ones use more.
+const POWER_COSTS: [i32; 3] = [1, 2, 3];
+// Wear on the cable and motor for each row travelled, out of 1.0 for worn out. An
+// overloaded car wears its cable twice as fast.
+const CABLE_WEAR: f32 = 0.002;
+const MOTOR_WEAR: f32 = 0.0015;
+// The chance of a breakdown in each row travelled, with a part worn out. Half worn
+// is a quarter as likely to fail.
+const BREAKDOWN_CHANCE: f32 = 0.01;
+// Rows a second gained every second, with nothing holding the car up.
+const GRAVITY: f32 = 20.0;
+// Repairs cost this much for a part that's completely worn, and more to fix a
+// breakdown.
+const WEAR_REPAIR_COST: f32 = 500.0;
+const BREAKDOWN_REPAIR_COST: i32 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Upgrade {
+    Motor,
+    Car,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Breakdown {
+    // The motor failed, and the car is stuck where it is.
+    Stuck,
+    // The cable snapped, and the car drops to the bottom of the shaft.
+    Snapped,
+}
+
+// The car's column and row are kept in a Position component alongside.
+#[derive(Component)]
 pub struct Elevator {
-    // The depth is the current location. Zero is ground level.
-    depth: i32,
     max_depth: i32,
-    // The target depth is the called location.
+    // The stop the car is heading for.
     target_depth: i32,
+    // Stops called from the levels, and the one the riders are heading for.
+    calls: BTreeSet<i32>,
+    ride: Option<i32>,
+    going_down: bool,
+    // Seconds left with the doors open.
+    hold: f32,
+    // The levels with a call button. The surface always has one.
+    buttons: BTreeSet<i32>,
+    // Where the car really is, between rows, and its speed downwards.
+    y: f32,
+    speed: f32,
+    motor: usize,
+    car: usize,
+    load: i32,
+    cable_wear: f32,
+    motor_wear: f32,
+    // Rows travelled since the last check for a breakdown, and since the last stop.
+    unchecked_rows: i32,
+    trip_rows: i32,
+    // Power used and not yet paid for.
+    bill: i32,
+    broken: Option<Breakdown>,
+    // Where a car with a snapped cable fell from, and how many rows it fell once it
+    // hit the bottom.
+    fell_from: f32,
+    crash: Option<i32>,
 }
 
 impl Elevator {
     pub fn new(max_depth: i32) -> Self {
         assert!(max_depth >= 0);
         Elevator {
-            depth: 0,
             max_depth,
             target_depth: 0,
+            calls: BTreeSet::new(),
+            ride: None,
+            going_down: true,
+            hold: 0.0,
+            buttons: BTreeSet::from([0]),
+            y: 0.0,
+            speed: 0.0,
+            motor: 0,
+            car: 0,
+            load: 0,
+            cable_wear: 0.0,
+            motor_wear: 0.0,
+            unchecked_rows: 0,
+            trip_rows: 0,
+            bill: 0,
+            broken: None,
+            fell_from: 0.0,
+            crash: None,
         }
     }
 
+    // Puts the car at rest at a level.
+    pub fn park(&mut self, depth: i32) {
+        self.y = self.clamp(depth) as f32;
+        self.target_depth = self.depth();
+        self.speed = 0.0;
+    }
+
+    // The row the car is nearest. Zero is ground level.
     pub fn depth(&self) -> i32 {
-        self.depth
+        self.y.round() as i32
+    }
+
+    fn clamp(&self, depth: i32) -> i32 {
+        core::cmp::max(0, core::cmp::min(depth, self.max_depth))
+    }
+
+    // Calls the car to a level. It gets there in turn.
+    pub fn request(&mut self, depth: i32) {
+        let depth = self.clamp(depth);
+        self.calls.insert(depth);
+    }
+
+    // Sends the car to a level, straight away.
+    pub fn send(&mut self, depth: i32) {
+        self.ride = Some(self.clamp(depth));
+        self.hold = 0.0;
+    }
+
+    // Sends the car on a row further up or down, for the riders.
+    pub fn ride(&mut self, rows: i32) {
+        let from = self.ride.unwrap_or_else(|| self.depth());
+        self.send(from + rows);
+    }
+
+    pub fn has_button(&self, depth: i32) -> bool {
+        self.buttons.contains(&depth)
+    }
+
+    pub fn add_button(&mut self, depth: i32) -> bool {
+        depth == self.clamp(depth) && self.buttons.insert(depth)
     }
 
-    pub fn set_target_depth(&mut self, target: i32) {
-        self.target_depth = core::cmp::max(0, core::cmp::min(target, self.max_depth));
+    pub fn is_called(&self, depth: i32) -> bool {
+        self.calls.contains(&depth)
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = i32> + '_ {
+        self.buttons.iter().copied()
+    }
+
+    // Riders come first. Otherwise the car carries on the way it's going while there
+    // are calls ahead that it can still stop for, and then turns around for the rest.
+    fn next_stop(&mut self) -> Option<i32> {
+        if let Some(stop) = self.ride {
+            self.going_down = stop as f32 > self.y;
+            return Some(stop);
+        }
+        let stops: Vec<i32> = self.calls.iter().copied().collect();
+        let dir = if self.going_down { 1.0 } else { -1.0 };
+        let stopping_at = self.y + dir * self.speed * self.speed / (2.0 * ACCELERATION);
+        // Once it's braking for a stop, it's sure to make it.
+        let (ahead, behind): (Vec<i32>, Vec<i32>) = stops.into_iter().partition(|&s| {
+            (s as f32 - stopping_at) * dir >= 0.0
+                || (s == self.target_depth && (s as f32 - self.y) * dir >= 0.0)
+        });
+        let (nearest_ahead, nearest_behind) = if self.going_down {
+            (ahead.into_iter().min(), behind.into_iter().max())
+        } else {
+            (ahead.into_iter().max(), behind.into_iter().min())
+        };
+        if nearest_ahead.is_none() && nearest_behind.is_some() {
+            self.going_down = !self.going_down;
+        }
+        nearest_ahead.or(nearest_behind)
+    }
+
+    // Holds at a stop for the doors, then heads for the next one. Returns the new
+    // depth.
+    pub fn update(&mut self, seconds: f32) -> i32 {
+        match self.broken {
+            Some(Breakdown::Stuck) => return self.depth(),
+            Some(Breakdown::Snapped) => {
+                self.plummet(seconds);
+                return self.depth();
+            }
+            None => {}
+        }
+        if self.hold > 0.0 {
+            self.hold = f32::max(0.0, self.hold - seconds);
+            return self.depth();
+        }
+        if let Some(stop) = self.next_stop() {
+            self.target_depth = stop;
+        }
+        let depth = self.depth();
+        self.move_towards_target(seconds);
+        self.wear((self.depth() - depth).abs());
+        if self.speed == 0.0 && self.y == self.target_depth as f32 {
+            let arrived = self.calls.remove(&self.target_depth);
+            if self.ride == Some(self.target_depth) {
+                self.ride = None;
+            } else if !arrived {
+                return self.depth();
+            }
+            self.hold = DOOR_TIME;
+            self.end_trip();
+        }
+        self.depth()
+    }
+
+    fn wear(&mut self, rows: i32) {
+        let overload = if self.load > self.capacity() {
+            2.0
+        } else {
+            1.0
+        };
+        self.cable_wear = f32::min(1.0, self.cable_wear + rows as f32 * CABLE_WEAR * overload);
+        self.motor_wear = f32::min(1.0, self.motor_wear + rows as f32 * MOTOR_WEAR);
+        self.unchecked_rows += rows;
+        self.trip_rows += rows;
+    }
+
+    fn end_trip(&mut self) {
+        self.bill += self.trip_rows * POWER_COSTS[self.motor];
+        self.trip_rows = 0;
+    }
+
+    // Every row travelled risks a breakdown, more so the more worn the parts are.
+    pub fn check_wear(&mut self, rng: &mut impl Rng) -> Option<Breakdown> {
+        let rows = std::mem::take(&mut self.unchecked_rows);
+        if self.broken.is_some() {
+            return None;
+        }
+        for _ in 0..rows {
+            let breakdown = if rng.gen::<f32>() < BREAKDOWN_CHANCE * self.cable_wear.powi(2) {
+                Breakdown::Snapped
+            } else if rng.gen::<f32>() < BREAKDOWN_CHANCE * self.motor_wear.powi(2) {
+                Breakdown::Stuck
+            } else {
+                continue;
+            };
+            self.break_down(breakdown);
+            return Some(breakdown);
+        }
+        None
     }
 
-    pub fn move_towards_target(&mut self) -> i32 {
-        if self.target_depth < self.depth {
-            self.depth -= 1;
-        } else if self.target_depth > self.depth {
-            self.depth += 1;
+    fn break_down(&mut self, breakdown: Breakdown) {
+        self.broken = Some(breakdown);
+        self.hold = 0.0;
+        self.fell_from = self.y;
+        if breakdown == Breakdown::Stuck {
+            self.speed = 0.0;
+        }
+        self.end_trip();
+    }
+
+    pub fn breakdown(&self) -> Option<Breakdown> {
+        self.broken
+    }
+
+    // With the cable gone, there's only one way to go.
+    fn plummet(&mut self, seconds: f32) {
+        let bottom = self.max_depth as f32;
+        if self.y >= bottom {
+            return;
+        }
+        self.speed += GRAVITY * seconds;
+        self.y = (self.y + self.speed * seconds).clamp(0.0, bottom);
+        // It may have been on its way up.
+        self.fell_from = f32::min(self.fell_from, self.y);
+        if self.y == bottom {
+            self.speed = 0.0;
+            self.crash = Some((bottom - self.fell_from).round() as i32);
+        }
+    }
+
+    // How many rows the car fell, once it hits the bottom.
+    pub fn take_crash(&mut self) -> Option<i32> {
+        self.crash.take()
+    }
+
+    // The power used since the last time it was paid for.
+    pub fn take_bill(&mut self) -> i32 {
+        std::mem::take(&mut self.bill)
+    }
+
+    // The riders' shares of a bill. They split it evenly, and whatever doesn't
+    // divide evenly falls to the first of them.
+    pub fn split_bill(bill: i32, riders: usize) -> Vec<i32> {
+        if riders == 0 {
+            return vec![];
+        }
+        let share = bill / riders as i32;
+        let mut shares = vec![share; riders];
+        shares[0] += bill - share * riders as i32;
+        shares
+    }
+
+    pub fn cable_wear(&self) -> f32 {
+        self.cable_wear
+    }
+
+    pub fn motor_wear(&self) -> f32 {
+        self.motor_wear
+    }
+
+    pub fn repair_cost(&self) -> i32 {
+        let wear = ((self.cable_wear + self.motor_wear) * WEAR_REPAIR_COST).round() as i32;
+        match self.broken {
+            Some(_) => wear + BREAKDOWN_REPAIR_COST,
+            None => wear,
+        }
+    }
+
+    // Good as new. A car stuck between rows is let down to the nearest one.
+    pub fn repair(&mut self) {
+        self.cable_wear = 0.0;
+        self.motor_wear = 0.0;
+        self.broken = None;
+        self.crash = None;
+        self.park(self.depth());
+    }
+
+    pub fn capacity(&self) -> i32 {
+        CAPACITIES[self.car]
+    }
+
+    // The weight of everyone aboard.
+    pub fn set_load(&mut self, load: i32) {
+        self.load = load;
+    }
+
+    pub fn top_speed(&self) -> f32 {
+        if self.load > self.capacity() {
+            TOP_SPEEDS[self.motor] / 2.0
+        } else {
+            TOP_SPEEDS[self.motor]
+        }
+    }
+
+    pub fn can_upgrade(&self, upgrade: Upgrade) -> bool {
+        match upgrade {
+            Upgrade::Motor => self.motor + 1 < TOP_SPEEDS.len(),
+            Upgrade::Car => self.car + 1 < CAPACITIES.len(),
+        }
+    }
+
+    pub fn upgrade(&mut self, upgrade: Upgrade) -> bool {
+        let ok = self.can_upgrade(upgrade);
+        if ok {
+            match upgrade {
+                Upgrade::Motor => self.motor += 1,
+                Upgrade::Car => self.car += 1,
+            }
+        }
+        ok
+    }
+
+    // Speeds up towards the target, up to the top speed, and brakes in time to stop
+    // there.
+    fn move_towards_target(&mut self, seconds: f32) {
+        let to_go = self.target_depth as f32 - self.y;
+        // Which way the car is going, or about to go.
+        let heading = if self.speed != 0.0 {
+            self.speed.signum()
+        } else if to_go != 0.0 {
+            to_go.signum()
+        } else {
+            return;
+        };
+        let speed = self.speed.abs();
+        let braking_distance = speed * speed / (2.0 * ACCELERATION);
+        let speed = if to_go * heading <= braking_distance || speed > self.top_speed() {
+            f32::max(0.0, speed - ACCELERATION * seconds)
+        } else {
+            f32::min(self.top_speed(), speed + ACCELERATION * seconds)
+        };
+        self.speed = heading * speed;
+        self.y += self.speed * seconds;
+
+        // Stop dead on arrival, rather than overshooting.
+        if to_go * heading > 0.0 && (self.target_depth as f32 - self.y) * heading <= 0.0 {
+            self.y = self.target_depth as f32;
+            self.speed = 0.0;
         }
-        self.depth
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::prelude::*;
+
+    const TICK: f32 = 0.05;
+
+    // Runs until the car next opens its doors, and returns where.
+    fn arrive(elev: &mut Elevator) -> Option<i32> {
+        for _ in 0..1000 {
+            let (depth, speed) = (elev.depth(), elev.speed.abs());
+            elev.update(TICK);
+            assert!((elev.depth() - depth).abs() <= 1);
+            assert!(elev.speed.abs() <= f32::max(speed, elev.top_speed()));
+            if elev.hold == DOOR_TIME {
+                return Some(elev.depth());
+            }
+        }
+        None
+    }
+
+    // Runs until every stop has been served, returning how many ticks it took.
+    fn run(elev: &mut Elevator) -> i32 {
+        let mut ticks = 0;
+        while ticks < 1000 && (elev.hold > 0.0 || elev.ride.is_some() || !elev.calls.is_empty()) {
+            elev.update(TICK);
+            ticks += 1;
+        }
+        ticks
+    }
 
     #[test]
     fn init() {
         let elev = Elevator::new(100);
         assert_eq!(0, elev.depth());
+        assert_eq!(0.0, elev.speed);
+        assert_eq!(0.0, elev.hold);
     }
 
     #[test]
     fn will_move() {
         let mut elev = Elevator::new(10);
 
-        elev.set_target_depth(2);
-        assert_eq!(0, elev.depth());
-        assert_eq!(1, elev.move_towards_target());
-        assert_eq!(2, elev.move_towards_target());
-        assert_eq!(2, elev.move_towards_target());
-        assert_eq!(2, elev.depth());
-
-        elev.set_target_depth(0);
-        assert_eq!(2, elev.depth());
-        assert_eq!(1, elev.move_towards_target());
-        assert_eq!(0, elev.move_towards_target());
-        assert_eq!(0, elev.move_towards_target());
+        elev.request(2);
         assert_eq!(0, elev.depth());
+        assert_eq!(Some(2), arrive(&mut elev));
+        run(&mut elev);
+        assert_eq!(2, elev.update(TICK));
+        assert_eq!(0.0, elev.speed);
 
-        elev.set_target_depth(100);
-        assert_eq!(0, elev.depth());
-        // It moves towards the target depth.
-        for i in 1..=10 {
-            assert_eq!(i, elev.move_towards_target());
+        elev.request(0);
+        assert_eq!(Some(0), arrive(&mut elev));
+
+        // It moves towards the target depth, but not beyond the max depth.
+        elev.request(100);
+        assert_eq!(Some(10), arrive(&mut elev));
+        run(&mut elev);
+        assert_eq!(10, elev.update(TICK));
+    }
+
+    #[test]
+    fn accelerates_and_brakes() {
+        let mut elev = Elevator::new(50);
+        elev.request(50);
+        let mut speeds = vec![];
+        while elev.y < 50.0 {
+            elev.update(TICK);
+            speeds.push(elev.speed);
+        }
+        // Picks up speed gently, cruises, and slows to a stop.
+        assert!(speeds[0] < 1.0);
+        assert!(speeds.windows(2).take(5).all(|w| w[1] > w[0]));
+        assert!(speeds.iter().all(|&s| s <= elev.top_speed()));
+        assert!(speeds.contains(&elev.top_speed()));
+        assert!(speeds[speeds.len() - 2] < 2.0);
+        assert_eq!(0.0, elev.speed);
+    }
+
+    #[test]
+    fn calls_are_not_lost() {
+        let mut elev = Elevator::new(50);
+        elev.request(20);
+        elev.update(TICK);
+        elev.request(10);
+        elev.request(10);
+        assert!(elev.is_called(10));
+        assert_eq!(Some(10), arrive(&mut elev));
+        assert!(!elev.is_called(10));
+        assert_eq!(Some(20), arrive(&mut elev));
+        assert_eq!(None, arrive(&mut elev));
+    }
+
+    #[test]
+    fn serves_stops_in_order() {
+        let mut elev = Elevator::new(50);
+        elev.request(50);
+        while elev.depth() < 15 {
+            elev.update(TICK);
         }
-        // But it doesn't move beyond the max depth.
-        assert_eq!(10, elev.move_towards_target());
-        assert_eq!(10, elev.move_towards_target());
+        // It stops for the call ahead on the way down, and comes back up for the other.
+        elev.request(5);
+        elev.request(30);
+        assert_eq!(Some(30), arrive(&mut elev));
+        elev.request(40);
+        assert_eq!(Some(40), arrive(&mut elev));
+        assert_eq!(Some(50), arrive(&mut elev));
+        assert_eq!(Some(5), arrive(&mut elev));
+    }
+
+    #[test]
+    fn too_late_to_stop() {
+        let mut elev = Elevator::new(50);
+        elev.request(50);
+        while elev.speed < elev.top_speed() {
+            elev.update(TICK);
+        }
+        // Going flat out, it can't stop for the very next row, so that has to wait.
+        let next = elev.depth() + 1;
+        elev.request(next);
+        assert_eq!(Some(50), arrive(&mut elev));
+        assert_eq!(Some(next), arrive(&mut elev));
+    }
+
+    #[test]
+    fn doors_hold() {
+        let mut elev = Elevator::new(50);
+        elev.request(0);
+        assert_eq!(Some(0), arrive(&mut elev));
+        elev.request(5);
+        for _ in 0..(DOOR_TIME / TICK) as i32 - 1 {
+            elev.update(TICK);
+            assert!(elev.hold > 0.0);
+            assert_eq!(0, elev.depth());
+        }
+        assert_eq!(Some(5), arrive(&mut elev));
+        // Riders don't have to wait for them.
+        elev.ride(1);
+        assert_eq!(0.0, elev.hold);
+        assert_eq!(Some(6), arrive(&mut elev));
+    }
+
+    #[test]
+    fn riding() {
+        let mut elev = Elevator::new(50);
+        // Keeping the button down runs on without stopping at every row.
+        for _ in 0..10 {
+            elev.ride(1);
+            elev.update(TICK);
+        }
+        assert_eq!(Some(10), arrive(&mut elev));
+        elev.ride(-1);
+        elev.ride(-1);
+        assert_eq!(Some(8), arrive(&mut elev));
+        elev.ride(-100);
+        assert_eq!(Some(0), arrive(&mut elev));
+    }
+
+    #[test]
+    fn sent_home() {
+        let mut elev = Elevator::new(50);
+        elev.request(30);
+        elev.update(TICK);
+        elev.send(0);
+        assert_eq!(Some(0), arrive(&mut elev));
+        assert_eq!(Some(30), arrive(&mut elev));
+    }
+
+    #[test]
+    fn parked() {
+        let mut elev = Elevator::new(10);
+        elev.park(4);
+        assert_eq!(4, elev.depth());
+        assert_eq!(None, arrive(&mut elev));
+        elev.request(0);
+        assert_eq!(Some(0), arrive(&mut elev));
+        elev.park(20);
         assert_eq!(10, elev.depth());
     }
 
+    #[test]
+    fn buttons() {
+        let mut elev = Elevator::new(50);
+        assert!(elev.has_button(0));
+        assert!(!elev.has_button(10));
+        assert!(elev.add_button(10));
+        assert!(!elev.add_button(10));
+        assert!(!elev.add_button(51));
+        assert!(!elev.add_button(-1));
+        assert_eq!(vec![0, 10], elev.buttons().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn power_is_billed_per_trip() {
+        let mut elev = Elevator::new(50);
+        elev.request(10);
+        elev.update(TICK);
+        assert_eq!(0, elev.take_bill());
+        assert_eq!(Some(10), arrive(&mut elev));
+        assert_eq!(10 * POWER_COSTS[0], elev.take_bill());
+        assert_eq!(0, elev.take_bill());
+
+        // Faster motors use more.
+        elev.upgrade(Upgrade::Motor);
+        elev.request(0);
+        arrive(&mut elev);
+        assert_eq!(10 * POWER_COSTS[1], elev.take_bill());
+    }
+
+    #[test]
+    fn splitting_the_bill() {
+        assert!(Elevator::split_bill(10, 0).is_empty());
+        assert_eq!(vec![10], Elevator::split_bill(10, 1));
+        assert_eq!(vec![5, 5], Elevator::split_bill(10, 2));
+        assert_eq!(vec![6, 5], Elevator::split_bill(11, 2));
+        assert_eq!(vec![1, 0], Elevator::split_bill(1, 2));
+    }
+
+    #[test]
+    fn wearing_out() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut elev = Elevator::new(50);
+        elev.request(50);
+        arrive(&mut elev);
+        assert!((elev.cable_wear() - 50.0 * CABLE_WEAR).abs() < 0.0001);
+        assert!((elev.motor_wear() - 50.0 * MOTOR_WEAR).abs() < 0.0001);
+
+        // New parts are unlikely to fail. Worn out ones will sooner or later.
+        assert_eq!(None, elev.check_wear(&mut rng));
+        assert_eq!(None, elev.check_wear(&mut rng));
+        elev.cable_wear = 1.0;
+        let mut trips = 0;
+        while elev.breakdown().is_none() && trips < 100 {
+            elev.request(if elev.depth() == 0 { 50 } else { 0 });
+            while elev.breakdown().is_none() && arrive(&mut elev).is_none() {}
+            elev.check_wear(&mut rng);
+            trips += 1;
+        }
+        assert!(elev.breakdown().is_some());
+    }
+
+    #[test]
+    fn overloading_wears_the_cable() {
+        let mut light = Elevator::new(50);
+        let mut heavy = Elevator::new(50);
+        heavy.set_load(heavy.capacity() + 1);
+        light.request(10);
+        heavy.request(10);
+        run(&mut light);
+        run(&mut heavy);
+        assert!((2.0 * light.cable_wear() - heavy.cable_wear()).abs() < 0.0001);
+        assert_eq!(light.motor_wear(), heavy.motor_wear());
+    }
+
+    #[test]
+    fn stuck() {
+        let mut elev = Elevator::new(50);
+        elev.request(20);
+        for _ in 0..10 {
+            elev.update(TICK);
+        }
+        elev.break_down(Breakdown::Stuck);
+        let y = elev.y;
+        elev.request(0);
+        for _ in 0..100 {
+            elev.update(TICK);
+        }
+        assert_eq!(y, elev.y);
+        assert_eq!(None, elev.take_crash());
+
+        let cost = elev.repair_cost();
+        assert!(cost > BREAKDOWN_REPAIR_COST);
+        elev.repair();
+        assert_eq!(None, elev.breakdown());
+        assert_eq!(0, elev.repair_cost());
+        assert_eq!(elev.depth() as f32, elev.y);
+        // And off it goes again.
+        assert_eq!(Some(20), arrive(&mut elev));
+        assert_eq!(Some(0), arrive(&mut elev));
+    }
+
+    #[test]
+    fn snapped() {
+        let mut elev = Elevator::new(50);
+        elev.request(10);
+        arrive(&mut elev);
+        elev.break_down(Breakdown::Snapped);
+        elev.request(0);
+        let mut speed = 0.0;
+        while elev.take_crash().is_none() {
+            let depth = elev.depth();
+            elev.update(TICK);
+            assert!(elev.speed >= speed || elev.depth() == 50);
+            assert!(elev.depth() >= depth);
+            speed = elev.speed;
+        }
+        assert_eq!(50, elev.depth());
+        assert_eq!(0.0, elev.speed);
+        // It fell 40 rows, and that's only reported once.
+        assert_eq!(Some(Breakdown::Snapped), elev.breakdown());
+        elev.update(TICK);
+        assert_eq!(None, elev.take_crash());
+        assert_eq!(50, elev.depth());
+
+        elev.repair();
+        assert_eq!(Some(0), arrive(&mut elev));
+    }
+
+    #[test]
+    fn crash_depth() {
+        let mut elev = Elevator::new(50);
+        elev.request(10);
+        arrive(&mut elev);
+        elev.break_down(Breakdown::Snapped);
+        let mut crash = None;
+        while crash.is_none() {
+            elev.update(TICK);
+            crash = elev.take_crash();
+        }
+        assert_eq!(Some(40), crash);
+    }
+
+    #[test]
+    fn upgrades() {
+        let mut slow = Elevator::new(50);
+        let mut fast = Elevator::new(50);
+        assert!(fast.upgrade(Upgrade::Motor));
+        assert!(fast.upgrade(Upgrade::Motor));
+        assert!(!fast.can_upgrade(Upgrade::Motor));
+        assert!(!fast.upgrade(Upgrade::Motor));
+        slow.request(50);
+        fast.request(50);
+        assert!(run(&mut fast) < run(&mut slow));
+        assert_eq!(50, fast.depth());
+    }
+
+    #[test]
+    fn overloaded() {
+        let mut elev = Elevator::new(50);
+        let top_speed = elev.top_speed();
+        elev.set_load(elev.capacity() + 1);
+        assert_eq!(top_speed / 2.0, elev.top_speed());
+        assert!(elev.upgrade(Upgrade::Car));
+        assert!(!elev.can_upgrade(Upgrade::Car));
+        assert_eq!(top_speed, elev.top_speed());
+    }
+
     #[test]
     fn null_range() {
         let mut elev = Elevator::new(0);
-        elev.set_target_depth(2);
-        assert_eq!(0, elev.depth());
-        assert_eq!(0, elev.move_towards_target());
+        elev.request(2);
+        assert_eq!(Some(0), arrive(&mut elev));
         assert_eq!(0, elev.depth());
+        assert_eq!(0.0, elev.speed);
     }
 }
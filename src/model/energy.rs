@@ -0,0 +1,107 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+// Bulk discounts, as (first unit, price per unit). Like tax brackets, each price
+// only applies to the units past the start of its tier.
+pub const TIERS: [(i32, i32); 3] = [(0, 10), (25, 9), (50, 8)];
+
+// What the bank sells at a time, by amount and by budget.
+pub const ENERGY_STEP: i32 = 10;
+pub const BUDGET_STEP: i32 = 100;
+
+// The price of n units of energy, bought in one go.
+pub fn cost(n: i32) -> i32 {
+    TIERS
+        .iter()
+        .enumerate()
+        .map(|(i, &(start, price))| {
+            let end = TIERS.get(i + 1).map_or(i32::MAX, |t| t.0);
+            (n.clamp(start, end) - start) * price
+        })
+        .sum()
+}
+
+// The most units of energy that a budget pays for.
+pub fn units_for(budget: i32) -> i32 {
+    let mut units = 0;
+    let mut left = core::cmp::max(0, budget);
+    for (i, &(start, price)) in TIERS.iter().enumerate() {
+        match TIERS.get(i + 1) {
+            Some(&(end, _)) if left >= (end - start) * price => {
+                units += end - start;
+                left -= (end - start) * price;
+            }
+            _ => return units + left / price,
+        }
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_works() {
+        assert_eq!(0, cost(0));
+        assert_eq!(0, cost(-5));
+        assert_eq!(10, cost(1));
+        assert_eq!(240, cost(24));
+        assert_eq!(250, cost(25));
+        assert_eq!(259, cost(26));
+        assert_eq!(475, cost(50));
+        assert_eq!(483, cost(51));
+        assert_eq!(875, cost(100));
+    }
+
+    #[test]
+    fn units_for_works() {
+        assert_eq!(0, units_for(-10));
+        assert_eq!(0, units_for(0));
+        assert_eq!(0, units_for(9));
+        assert_eq!(1, units_for(10));
+        assert_eq!(1, units_for(19));
+        assert_eq!(24, units_for(249));
+        assert_eq!(25, units_for(250));
+        assert_eq!(25, units_for(258));
+        assert_eq!(26, units_for(259));
+        assert_eq!(49, units_for(474));
+        assert_eq!(50, units_for(475));
+        assert_eq!(50, units_for(482));
+        assert_eq!(51, units_for(483));
+        assert_eq!(100, units_for(875));
+    }
+
+    #[test]
+    fn units_for_is_the_inverse_of_cost() {
+        for budget in 0..2000 {
+            let n = units_for(budget);
+            assert!(cost(n) <= budget, "budget {}", budget);
+            assert!(cost(n + 1) > budget, "budget {}", budget);
+        }
+        for n in 0..200 {
+            assert_eq!(n, units_for(cost(n)));
+        }
+    }
+
+    #[test]
+    fn bulk_is_cheaper() {
+        for n in 1..200 {
+            assert!(cost(n) - cost(n - 1) <= cost(1));
+            assert!(cost(n + 1) - cost(n) <= cost(n) - cost(n - 1));
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::map::{Map, TileType};
+use rand::Rng;
+
+// Water breaks into the tunnels around (x, y), sparing the given tiles.
+pub fn flood(map: &mut Map, x: i32, y: i32, spared: &[(i32, i32)], rng: &mut impl Rng) {
+    for (tx, ty) in map.rect(
+        x - HAZARD_RANGE,
+        y - HAZARD_RANGE,
+        x + HAZARD_RANGE,
+        y + HAZARD_RANGE,
+    ) {
+        if rng.gen_range(0..100) < 10
+            && matches!(map.tile(tx, ty), TileType::Empty | TileType::Dirt)
+            && !spared.contains(&(tx, ty))
+            && tx < ELEVATOR_SHAFT_X
+            && !map.is_shaft(tx)
+        {
+            map.set_tile(tx, ty, TileType::Water);
+        }
+    }
+}
+
+// The tunnels around (x, y) fill back in with dirt, sparing the given tiles so
+// nobody is buried.
+pub fn cave_in(map: &mut Map, x: i32, y: i32, spared: &[(i32, i32)], rng: &mut impl Rng) {
+    for (tx, ty) in map.rect(
+        x - HAZARD_RANGE,
+        y - HAZARD_RANGE,
+        x + HAZARD_RANGE,
+        y + HAZARD_RANGE,
+    ) {
+        if rng.gen_range(0..100) < 50
+            && matches!(map.tile(tx, ty), TileType::Empty | TileType::Ladder)
+            && !spared.contains(&(tx, ty))
+            && tx < ELEVATOR_SHAFT_X
+            && !map.is_shaft(tx)
+        {
+            map.set_tile(tx, ty, TileType::Dirt);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Air {
+    // At the surface, or in an elevator. It fills the lungs back up.
+    Fresh,
+    // Enough to get by on.
+    Still,
+    // Deep down, with no open tunnel to a shaft.
+    Stale,
+    Underwater,
+}
+
+// What there is to breathe at (x, y).
+pub fn air(map: &Map, x: i32, y: i32, in_elevator: bool) -> Air {
+    if y <= SKY_HEIGHT || in_elevator {
+        Air::Fresh
+    } else if map.tile(x, y) == TileType::Water {
+        Air::Underwater
+    } else if y - GRASS_LEVEL > VENTILATED_DEPTH && !ventilated(map, x, y) {
+        Air::Stale
+    } else {
+        Air::Still
+    }
+}
+
+// The elevator shafts are the only air supply deep down, and it reaches as far as
+// the tunnels open to them.
+fn ventilated(map: &Map, x: i32, y: i32) -> bool {
+    let open = |t| matches!(t, TileType::Empty | TileType::Ladder | TileType::Pump);
+    map.shafts()
+        .any(|sx| map.is_connected((x, y), (sx, GRASS_LEVEL), open))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    // A map with a shaft built down column 10, and the dirt around it dug out.
+    fn shaft_map() -> Map {
+        let mut m = Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
+        for y in GRASS_LEVEL..GRASS_LEVEL + 40 {
+            for x in 5..=15 {
+                m.set_tile(x, y, TileType::Empty);
+            }
+        }
+        assert!(m.build_shaft(10).is_some());
+        m
+    }
+
+    #[test]
+    fn floods_spare_shafts() {
+        let mut m = shaft_map();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            flood(
+                &mut m,
+                10,
+                GRASS_LEVEL + 10,
+                &[(9, GRASS_LEVEL + 10)],
+                &mut rng,
+            );
+        }
+        assert!((GRASS_LEVEL..GRASS_LEVEL + 40).all(|y| m.tile(10, y) == TileType::Empty));
+        assert_eq!(TileType::Empty, m.tile(9, GRASS_LEVEL + 10));
+        assert_eq!(TileType::Water, m.tile(11, GRASS_LEVEL + 10));
+    }
+
+    #[test]
+    fn cave_ins_spare_shafts() {
+        let mut m = shaft_map();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            cave_in(&mut m, 10, GRASS_LEVEL + 10, &[], &mut rng);
+        }
+        assert!((GRASS_LEVEL..GRASS_LEVEL + 40).all(|y| m.tile(10, y) == TileType::Empty));
+        assert_eq!(TileType::Dirt, m.tile(11, GRASS_LEVEL + 10));
+    }
+
+    #[test]
+    fn breathing() {
+        let mut m = shaft_map();
+        let deep = GRASS_LEVEL + VENTILATED_DEPTH + 5;
+        assert_eq!(Air::Fresh, air(&m, 12, SKY_HEIGHT, false));
+        assert_eq!(Air::Fresh, air(&m, 10, deep, true));
+        assert_eq!(Air::Still, air(&m, 12, GRASS_LEVEL + 5, false));
+        // The new shaft lets air down, too, into the tunnels open to it.
+        assert_eq!(Air::Still, air(&m, 10, deep, false));
+        assert_eq!(Air::Still, air(&m, 12, deep, false));
+        m.set_tile(12, deep, TileType::Water);
+        assert_eq!(Air::Underwater, air(&m, 12, deep, false));
+
+        // A wall of dirt seals off the far side.
+        for y in GRASS_LEVEL..GRASS_LEVEL + 40 {
+            m.set_tile(13, y, TileType::Dirt);
+        }
+        assert_eq!(Air::Stale, air(&m, 14, deep, false));
+        assert_eq!(Air::Still, air(&m, 14, GRASS_LEVEL + 5, false));
+    }
+
+    #[test]
+    fn tunnels_off_a_shaft_are_ventilated() {
+        let mut m = Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
+        let deep = GRASS_LEVEL + VENTILATED_DEPTH + 5;
+        for y in GRASS_LEVEL..=deep {
+            m.set_tile(10, y, TileType::Empty);
+        }
+        assert!(m.build_shaft(10).is_some());
+        // A tunnel branching off the bottom of the shaft, with a ladder in it.
+        for x in 11..=20 {
+            m.set_tile(x, deep, TileType::Empty);
+        }
+        m.set_tile(15, deep, TileType::Ladder);
+        assert_eq!(Air::Still, air(&m, 11, deep, false));
+        assert_eq!(Air::Still, air(&m, 20, deep, false));
+
+        // Once it caves in, the far end goes stale.
+        m.set_tile(16, deep, TileType::Dirt);
+        assert_eq!(Air::Still, air(&m, 15, deep, false));
+        assert_eq!(Air::Stale, air(&m, 20, deep, false));
+    }
+}
@@ -0,0 +1,291 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::model::elevator::Upgrade;
+use crate::model::mineral::Mineral;
+use crate::model::pick::Pick;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Item {
+    Ore(Mineral),
+    Dynamite,
+    Ladder,
+    Lantern,
+    Pick(Pick),
+    Pump,
+    Timber,
+    Upgrade(Upgrade),
+}
+
+impl Item {
+    pub fn from_name(name: &str) -> Option<Item> {
+        match name {
+            "dynamite" => Some(Item::Dynamite),
+            "ladder" => Some(Item::Ladder),
+            "lantern" => Some(Item::Lantern),
+            "iron_pick" => Some(Item::Pick(Pick::Iron)),
+            "steel_pick" => Some(Item::Pick(Pick::Steel)),
+            "diamond_pick" => Some(Item::Pick(Pick::Diamond)),
+            "pump" => Some(Item::Pump),
+            "timber" => Some(Item::Timber),
+            "elevator_motor" => Some(Item::Upgrade(Upgrade::Motor)),
+            "elevator_car" => Some(Item::Upgrade(Upgrade::Car)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Item::Ore(m) => m.name(),
+            Item::Dynamite => "Dynamite",
+            Item::Ladder => "Ladder",
+            Item::Lantern => "Lantern",
+            Item::Pick(Pick::Bronze) => "Bronze pick",
+            Item::Pick(Pick::Iron) => "Iron pick",
+            Item::Pick(Pick::Steel) => "Steel pick",
+            Item::Pick(Pick::Diamond) => "Diamond pick",
+            Item::Pump => "Pump",
+            Item::Timber => "Timber",
+            Item::Upgrade(Upgrade::Motor) => "Elevator motor",
+            Item::Upgrade(Upgrade::Car) => "Elevator car",
+        }
+    }
+
+    // Tools work just by being carried, so one is enough.
+    pub fn is_tool(&self) -> bool {
+        matches!(self, Item::Lantern)
+    }
+
+    pub fn weight(&self) -> i32 {
+        match self {
+            Item::Ore(Mineral::Coal) => 3,
+            Item::Ore(Mineral::Copper) => 4,
+            Item::Ore(Mineral::Silver) => 5,
+            Item::Ore(Mineral::Gold) => 8,
+            Item::Ore(Mineral::Ruby) | Item::Ore(Mineral::Diamond) => 1,
+            Item::Dynamite => 1,
+            Item::Ladder => 2,
+            Item::Lantern => 1,
+            Item::Pump => 10,
+            Item::Timber => 3,
+            // Fitted straight away, rather than carried.
+            Item::Pick(_) | Item::Upgrade(_) => 0,
+        }
+    }
+
+    pub fn volume(&self) -> i32 {
+        match self {
+            Item::Ore(Mineral::Coal) => 3,
+            Item::Ore(Mineral::Copper) | Item::Ore(Mineral::Silver) => 2,
+            Item::Ore(_) => 1,
+            Item::Dynamite => 1,
+            Item::Ladder => 2,
+            Item::Lantern => 1,
+            Item::Pump => 6,
+            Item::Timber => 3,
+            Item::Pick(_) | Item::Upgrade(_) => 0,
+        }
+    }
+}
+
+pub struct Inventory {
+    items: BTreeMap<Item, i32>,
+    max_weight: i32,
+    max_volume: i32,
+}
+
+impl Inventory {
+    pub fn new(max_weight: i32, max_volume: i32) -> Self {
+        Inventory {
+            items: BTreeMap::new(),
+            max_weight,
+            max_volume,
+        }
+    }
+
+    pub fn count(&self, item: Item) -> i32 {
+        self.items.get(&item).copied().unwrap_or(0)
+    }
+
+    pub fn weight(&self) -> i32 {
+        self.items.iter().map(|(i, n)| i.weight() * n).sum()
+    }
+
+    pub fn max_weight(&self) -> i32 {
+        self.max_weight
+    }
+
+    pub fn volume(&self) -> i32 {
+        self.items.iter().map(|(i, n)| i.volume() * n).sum()
+    }
+
+    // How full it is, by whichever of weight or volume is closer to the limit.
+    pub fn load(&self) -> f32 {
+        f32::max(
+            self.weight() as f32 / self.max_weight as f32,
+            self.volume() as f32 / self.max_volume as f32,
+        )
+    }
+
+    pub fn can_add(&self, item: Item, n: i32) -> bool {
+        self.weight() + item.weight() * n <= self.max_weight
+            && self.volume() + item.volume() * n <= self.max_volume
+    }
+
+    pub fn add(&mut self, item: Item, n: i32) -> bool {
+        let ok = self.can_add(item, n);
+        if ok {
+            *self.items.entry(item).or_insert(0) += n;
+        }
+        ok
+    }
+
+    pub fn remove(&mut self, item: Item, n: i32) -> bool {
+        let ok = self.count(item) >= n;
+        if ok {
+            *self.items.entry(item).or_insert(0) -= n;
+            self.items.retain(|_, n| *n > 0);
+        }
+        ok
+    }
+
+    // Takes out the heaviest piece of ore, to lighten the load.
+    pub fn drop_heaviest_ore(&mut self) -> Option<Mineral> {
+        let mineral = self
+            .items
+            .keys()
+            .filter_map(|i| match i {
+                Item::Ore(m) => Some(*m),
+                _ => None,
+            })
+            .max_by_key(|m| (Item::Ore(*m).weight(), Item::Ore(*m).volume()))?;
+        self.remove(Item::Ore(mineral), 1);
+        Some(mineral)
+    }
+
+    // Takes out all of the ore, leaving everything else.
+    pub fn take_ore(&mut self) -> Vec<(Mineral, i32)> {
+        let ore = self
+            .items
+            .iter()
+            .filter_map(|(i, n)| match i {
+                Item::Ore(m) => Some((*m, *n)),
+                _ => None,
+            })
+            .collect();
+        self.items.retain(|i, _| !matches!(i, Item::Ore(_)));
+        ore
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_work() {
+        for item in [
+            Item::Dynamite,
+            Item::Ladder,
+            Item::Lantern,
+            Item::Pick(Pick::Iron),
+            Item::Pick(Pick::Steel),
+            Item::Pick(Pick::Diamond),
+            Item::Pump,
+            Item::Timber,
+        ] {
+            let name = item.name().to_lowercase().replace(' ', "_");
+            assert_eq!(Some(item), Item::from_name(&name));
+        }
+        assert_eq!(None, Item::from_name("gold"));
+        // Everyone starts with one of these.
+        assert_eq!(None, Item::from_name("bronze_pick"));
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let mut inv = Inventory::new(100, 100);
+        assert_eq!(0, inv.count(Item::Pump));
+        assert!(inv.add(Item::Pump, 2));
+        assert!(inv.add(Item::Ore(Mineral::Gold), 1));
+        assert_eq!(2, inv.count(Item::Pump));
+        assert_eq!(2 * 10 + 8, inv.weight());
+        assert_eq!(2 * 6 + 1, inv.volume());
+
+        assert!(!inv.remove(Item::Pump, 3));
+        assert_eq!(2, inv.count(Item::Pump));
+        assert!(inv.remove(Item::Pump, 2));
+        assert_eq!(0, inv.count(Item::Pump));
+        assert!(!inv.remove(Item::Pump, 1));
+        assert_eq!(8, inv.weight());
+    }
+
+    #[test]
+    fn weight_limit() {
+        let mut inv = Inventory::new(20, 100);
+        assert!(inv.add(Item::Ore(Mineral::Gold), 2));
+        assert!(!inv.can_add(Item::Ore(Mineral::Gold), 1));
+        assert!(!inv.add(Item::Ore(Mineral::Gold), 1));
+        assert!(inv.add(Item::Ore(Mineral::Ruby), 4));
+        assert_eq!(20, inv.weight());
+        assert!(!inv.add(Item::Ore(Mineral::Diamond), 1));
+        assert_eq!(1.0, inv.load());
+    }
+
+    #[test]
+    fn volume_limit() {
+        let mut inv = Inventory::new(100, 6);
+        assert!(inv.add(Item::Ore(Mineral::Coal), 2));
+        assert!(!inv.add(Item::Ore(Mineral::Diamond), 1));
+        assert_eq!(6, inv.weight());
+        assert_eq!(1.0, inv.load());
+    }
+
+    #[test]
+    fn load_works() {
+        let mut inv = Inventory::new(10, 100);
+        assert_eq!(0.0, inv.load());
+        inv.add(Item::Ore(Mineral::Copper), 1);
+        assert_eq!(0.4, inv.load());
+    }
+
+    #[test]
+    fn drop_heaviest_ore() {
+        let mut inv = Inventory::new(100, 100);
+        inv.add(Item::Ore(Mineral::Coal), 1);
+        inv.add(Item::Ore(Mineral::Gold), 2);
+        inv.add(Item::Pump, 1);
+        assert_eq!(Some(Mineral::Gold), inv.drop_heaviest_ore());
+        assert_eq!(Some(Mineral::Gold), inv.drop_heaviest_ore());
+        assert_eq!(Some(Mineral::Coal), inv.drop_heaviest_ore());
+        // Anything else is kept.
+        assert_eq!(None, inv.drop_heaviest_ore());
+        assert_eq!(1, inv.count(Item::Pump));
+    }
+
+    #[test]
+    fn take_ore() {
+        let mut inv = Inventory::new(100, 100);
+        inv.add(Item::Pump, 1);
+        inv.add(Item::Ore(Mineral::Gold), 2);
+        inv.add(Item::Ore(Mineral::Coal), 3);
+        assert_eq!(vec![(Mineral::Coal, 3), (Mineral::Gold, 2)], inv.take_ore());
+        assert!(inv.take_ore().is_empty());
+        assert_eq!(1, inv.count(Item::Pump));
+        assert_eq!(10, inv.weight());
+    }
+}
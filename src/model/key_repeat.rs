@@ -0,0 +1,284 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::position::{Position, Target};
+use bevy::prelude::Component;
+use std::collections::VecDeque;
+
+// Presses waiting for the next tick. Any more than this are dropped.
+const BUFFER_SIZE: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    // Where a step this way leads, staying inside the mine.
+    pub fn target(&self, pos: Position) -> Target {
+        let mut target = pos.target();
+        match self {
+            Direction::Left if pos.x > 1 => target.x -= 1,
+            Direction::Right if pos.x < MAP_WIDTH - 3 => target.x += 1,
+            Direction::Up if pos.y > SKY_HEIGHT => target.y -= 1,
+            Direction::Down if pos.y < MAP_HEIGHT - 2 => target.y += 1,
+            _ => {}
+        }
+        target
+    }
+}
+
+// How held keys repeat.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RepeatSettings {
+    // Seconds before the first repeat.
+    pub delay: f32,
+    // Repeats a second, after that.
+    pub rate: f32,
+}
+
+impl Default for RepeatSettings {
+    fn default() -> Self {
+        RepeatSettings {
+            delay: 0.3,
+            rate: 8.0,
+        }
+    }
+}
+
+impl RepeatSettings {
+    // Takes out the "--repeat-delay" (in milliseconds) and "--repeat-rate" (steps a second)
+    // options, and returns the rest of the arguments.
+    pub fn from_args(args: &[String]) -> Result<(Self, Vec<String>), String> {
+        let mut settings = RepeatSettings::default();
+        let mut rest = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = match arg.as_str() {
+                "--repeat-delay" | "--repeat-rate" => args
+                    .next()
+                    .ok_or(format!("Missing value for {}", arg))?
+                    .parse::<f32>()
+                    .map_err(|e| format!("Bad value for {}: {}", arg, e))?,
+                _ => {
+                    rest.push(arg.clone());
+                    continue;
+                }
+            };
+            match arg.as_str() {
+                "--repeat-delay" if value >= 0.0 => settings.delay = value / 1000.0,
+                "--repeat-rate" if value > 0.0 => settings.rate = value,
+                _ => return Err(format!("Out of range value for {}: {}", arg, value)),
+            }
+        }
+        Ok((settings, rest))
+    }
+}
+
+// Turns a held key into a stream of presses: one straight away, then more after
+// a delay, at a steady rate.
+#[derive(Component, Default)]
+pub struct KeyRepeat {
+    held: Option<Direction>,
+    // Seconds the key has been held, and when it repeats next.
+    time: f32,
+    next: f32,
+}
+
+impl KeyRepeat {
+    // Takes the key that was just pressed, if any, and the one being held down.
+    // Returns a press, and whether it is a repeat.
+    pub fn update(
+        &mut self,
+        pressed: Option<Direction>,
+        held: Option<Direction>,
+        settings: &RepeatSettings,
+        seconds: f32,
+    ) -> Option<(Direction, bool)> {
+        if let Some(dir) = pressed {
+            self.held = Some(dir);
+            self.time = 0.0;
+            self.next = settings.delay;
+            return Some((dir, false));
+        }
+        if held != self.held {
+            // Let go, or let go of one of two keys. Either way, start over.
+            self.held = held;
+            self.time = 0.0;
+            self.next = settings.delay;
+            return None;
+        }
+        let dir = self.held?;
+        self.time += seconds;
+        if self.time < self.next {
+            return None;
+        }
+        self.next += 1.0 / settings.rate;
+        Some((dir, true))
+    }
+}
+
+// Moves pressed between ticks, so that none are lost.
+#[derive(Component, Default)]
+pub struct MoveBuffer {
+    moves: VecDeque<Direction>,
+}
+
+impl MoveBuffer {
+    // Repeats only wait their turn when nothing else is, so that a slow player
+    // doesn't keep going after the key is let go.
+    pub fn push(&mut self, dir: Direction, repeat: bool) {
+        let room = if repeat { 1 } else { BUFFER_SIZE };
+        if self.moves.len() < room {
+            self.moves.push_back(dir);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.moves.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn targets() {
+        let p = Position::new(10, 10);
+        assert_eq!(Target { x: 9, y: 10 }, Direction::Left.target(p));
+        assert_eq!(Target { x: 11, y: 10 }, Direction::Right.target(p));
+        assert_eq!(Target { x: 10, y: 9 }, Direction::Up.target(p));
+        assert_eq!(Target { x: 10, y: 11 }, Direction::Down.target(p));
+        // Not out of the mine, or up into the sky.
+        assert_eq!(
+            Target {
+                x: 1,
+                y: SKY_HEIGHT
+            },
+            Direction::Left.target(Position::new(1, SKY_HEIGHT))
+        );
+        assert_eq!(
+            Target {
+                x: 1,
+                y: SKY_HEIGHT
+            },
+            Direction::Up.target(Position::new(1, SKY_HEIGHT))
+        );
+    }
+
+    #[test]
+    fn settings() {
+        let (s, rest) = RepeatSettings::from_args(&args("--seed 7")).unwrap();
+        assert_eq!(RepeatSettings::default(), s);
+        assert_eq!(args("--seed 7"), rest);
+
+        let (s, rest) =
+            RepeatSettings::from_args(&args("--repeat-delay 500 --name Zoe --repeat-rate 20"))
+                .unwrap();
+        assert_eq!(0.5, s.delay);
+        assert_eq!(20.0, s.rate);
+        assert_eq!(args("--name Zoe"), rest);
+
+        assert!(RepeatSettings::from_args(&args("--repeat-delay")).is_err());
+        assert!(RepeatSettings::from_args(&args("--repeat-delay soon")).is_err());
+        assert!(RepeatSettings::from_args(&args("--repeat-rate 0")).is_err());
+        assert!(RepeatSettings::from_args(&args("--repeat-delay -1")).is_err());
+    }
+
+    #[test]
+    fn repeating() {
+        let s = RepeatSettings {
+            delay: 0.5,
+            rate: 10.0,
+        };
+        let mut r = KeyRepeat::default();
+        let left = Some(Direction::Left);
+        assert_eq!(None, r.update(None, None, &s, 0.1));
+        assert_eq!(
+            Some((Direction::Left, false)),
+            r.update(left, left, &s, 0.1)
+        );
+        // Nothing until the delay is up.
+        for _ in 0..4 {
+            assert_eq!(None, r.update(None, left, &s, 0.1));
+        }
+        assert_eq!(
+            Some((Direction::Left, true)),
+            r.update(None, left, &s, 0.11)
+        );
+        // Then steadily.
+        assert_eq!(None, r.update(None, left, &s, 0.05));
+        assert_eq!(
+            Some((Direction::Left, true)),
+            r.update(None, left, &s, 0.05)
+        );
+        // Letting go stops it.
+        assert_eq!(None, r.update(None, None, &s, 1.0));
+        assert_eq!(None, r.update(None, None, &s, 1.0));
+    }
+
+    #[test]
+    fn quick_taps() {
+        let s = RepeatSettings::default();
+        let mut r = KeyRepeat::default();
+        let up = Some(Direction::Up);
+        // Pressed and let go within the same frame still counts.
+        assert_eq!(Some((Direction::Up, false)), r.update(up, None, &s, 0.01));
+        assert_eq!(None, r.update(None, None, &s, 0.01));
+        assert_eq!(Some((Direction::Up, false)), r.update(up, up, &s, 0.01));
+        // Switching keys starts over.
+        let down = Some(Direction::Down);
+        assert_eq!(
+            Some((Direction::Down, false)),
+            r.update(down, down, &s, 0.01)
+        );
+        assert_eq!(None, r.update(None, down, &s, s.delay - 0.02));
+    }
+
+    #[test]
+    fn buffering() {
+        let mut b = MoveBuffer::default();
+        assert_eq!(None, b.pop());
+        for dir in [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ] {
+            b.push(dir, false);
+        }
+        // Only so many are kept, in order.
+        assert_eq!(Some(Direction::Left), b.pop());
+        // Repeats don't pile up.
+        b.push(Direction::Down, true);
+        assert_eq!(Some(Direction::Up), b.pop());
+        assert_eq!(Some(Direction::Right), b.pop());
+        assert_eq!(None, b.pop());
+        b.push(Direction::Down, true);
+        b.push(Direction::Down, true);
+        assert_eq!(Some(Direction::Down), b.pop());
+        assert_eq!(None, b.pop());
+    }
+}
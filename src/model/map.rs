@@ -15,6 +15,11 @@
 #![warn(clippy::all)]
 
 use crate::constants::*;
+use crate::model::mineral::Mineral;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+// The shortest elevator shaft worth building, in rows below the surface.
+const MIN_SHAFT_RUN: i32 = 3;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TileType {
@@ -23,9 +28,10 @@ pub enum TileType {
     Empty,
     Grass,
     Ladder,
+    Pump,
     Rock { hardness: u8 },
     Sky,
-    Treasure { value: u8 },
+    Treasure { mineral: Mineral },
     Void,
     Water,
 }
@@ -34,6 +40,10 @@ pub struct Map {
     height: usize,
     width: usize,
     tiles: Vec<TileType>,
+    // Tiles that changed since the last call to take_dirty().
+    dirty: HashSet<(i32, i32)>,
+    // The columns with an elevator shaft.
+    shafts: BTreeSet<i32>,
 }
 
 impl Map {
@@ -42,30 +52,172 @@ impl Map {
             height,
             width,
             tiles: vec![TileType::Dirt; height * width],
+            dirty: HashSet::new(),
+            shafts: BTreeSet::new(),
         };
         map.init_default_tiles();
+        // Nothing has been drawn yet.
+        for x in 0..width as i32 {
+            for y in 0..height as i32 {
+                map.dirty.insert((x, y));
+            }
+        }
         map
     }
 
     pub fn tile(&self, x: i32, y: i32) -> TileType {
-        if x < 0 || x as usize >= self.width {
-            return TileType::Void;
-        }
-        if y < 0 || y as usize >= self.height {
+        if !self.in_bounds(x, y) {
             return TileType::Void;
         }
         self.tiles[self.tile_idx(x, y)]
     }
 
     pub fn set_tile(&mut self, x: i32, y: i32, t: TileType) {
-        if x < 0 || x as usize >= self.width {
-            panic!("Invalid tile location.");
-        }
-        if y < 0 || y as usize >= self.height {
+        if !self.in_bounds(x, y) {
             panic!("Invalid tile location.");
         }
         let idx = self.tile_idx(x, y);
-        self.tiles[idx] = t;
+        if self.tiles[idx] != t {
+            self.tiles[idx] = t;
+            self.dirty.insert((x, y));
+        }
+    }
+
+    // Returns the locations of all tiles changed since the last call.
+    pub fn take_dirty(&mut self) -> Vec<(i32, i32)> {
+        self.dirty.drain().collect()
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && (x as usize) < self.width && y >= 0 && (y as usize) < self.height
+    }
+
+    // The (up to) four tiles directly above, left, right and below.
+    pub fn neighbors(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> {
+        let (w, h) = (self.width as i32, self.height as i32);
+        [(x, y - 1), (x - 1, y), (x + 1, y), (x, y + 1)]
+            .into_iter()
+            .filter(move |&(nx, ny)| nx >= 0 && nx < w && ny >= 0 && ny < h)
+    }
+
+    // All tiles in the rectangle between the two corners (inclusive), clipped to the map.
+    pub fn rect(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> impl Iterator<Item = (i32, i32)> {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let xs = x0.min(x1).max(0)..=x0.max(x1).min(w - 1);
+        let ys = y0.min(y1).max(0)..=y0.max(y1).min(h - 1);
+        ys.flat_map(move |y| xs.clone().map(move |x| (x, y)))
+    }
+
+    // All tiles no further than r from (x, y), clipped to the map.
+    pub fn radius(&self, x: i32, y: i32, r: i32) -> impl Iterator<Item = (i32, i32)> {
+        self.rect(x - r, y - r, x + r, y + r)
+            .filter(move |&(tx, ty)| (tx - x) * (tx - x) + (ty - y) * (ty - y) <= r * r)
+    }
+
+    // The tiles that match the predicate and can be reached from (x, y) by stepping
+    // between them, in breadth-first order. The starting tile only counts if it
+    // matches too, but the search spreads out from it either way.
+    pub fn flood_fill<F>(&self, x: i32, y: i32, pred: F) -> Vec<(i32, i32)>
+    where
+        F: Fn(TileType) -> bool,
+    {
+        let mut seen = HashSet::from([(x, y)]);
+        let mut queue = VecDeque::from([(x, y)]);
+        let mut found = Vec::new();
+        while let Some((tx, ty)) = queue.pop_front() {
+            if (tx, ty) != (x, y) || pred(self.tile(x, y)) {
+                found.push((tx, ty));
+            }
+            for (nx, ny) in self.neighbors(tx, ty) {
+                if pred(self.tile(nx, ny)) && seen.insert((nx, ny)) {
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        found
+    }
+
+    // Whether there is a path between the two tiles through tiles that match the
+    // predicate. Both ends have to match as well.
+    pub fn is_connected<F>(&self, from: (i32, i32), to: (i32, i32), pred: F) -> bool
+    where
+        F: Fn(TileType) -> bool,
+    {
+        if !pred(self.tile(from.0, from.1)) || !pred(self.tile(to.0, to.1)) {
+            return false;
+        }
+        self.flood_fill(from.0, from.1, pred).contains(&to)
+    }
+
+    // Whether the straight line between the two tiles only passes through tiles that
+    // match the predicate. The end points themselves aren't checked.
+    pub fn line_of_sight<F>(&self, from: (i32, i32), to: (i32, i32), pred: F) -> bool
+    where
+        F: Fn(TileType) -> bool,
+    {
+        // Bresenham's line algorithm.
+        let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+        let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+        let (mut x, mut y) = from;
+        let mut err = dx + dy;
+        loop {
+            if (x, y) == to {
+                return true;
+            }
+            if (x, y) != from && !pred(self.tile(x, y)) {
+                return false;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn is_shaft(&self, x: i32) -> bool {
+        self.shafts.contains(&x)
+    }
+
+    pub fn shafts(&self) -> impl Iterator<Item = i32> + '_ {
+        self.shafts.iter().copied()
+    }
+
+    // How many rows of open tunnel run straight down from the surface in column x.
+    pub fn open_run(&self, x: i32) -> i32 {
+        (GRASS_LEVEL..self.height as i32 - 1)
+            .take_while(|&y| matches!(self.tile(x, y), TileType::Empty | TileType::Ladder))
+            .count() as i32
+    }
+
+    // Turns the open run in column x into an elevator shaft, returning how deep the
+    // car can go and how many ladders were taken down to make way for it. A shaft
+    // needs the columns either side for its tower and its call buttons, and can't go
+    // up in front of the bank.
+    pub fn build_shaft(&mut self, x: i32) -> Option<(i32, i32)> {
+        let run = self.open_run(x);
+        if x < 2
+            || x > self.width as i32 - 3
+            || (BANK_X - 1..=BANK_X + 1).contains(&x)
+            || (x - 2..=x + 2).any(|sx| self.is_shaft(sx))
+            || run < MIN_SHAFT_RUN
+        {
+            return None;
+        }
+        let mut ladders = 0;
+        for y in GRASS_LEVEL..GRASS_LEVEL + run {
+            if self.tile(x, y) == TileType::Ladder {
+                ladders += 1;
+            }
+            self.set_tile(x, y, TileType::Empty);
+        }
+        self.shafts.insert(x);
+        // The car stops at the surface and every row below.
+        Some((run, ladders))
     }
 
     fn tile_idx(&self, x: i32, y: i32) -> usize {
@@ -115,6 +267,7 @@ impl Map {
             // width-3 is the actual elevator shaft.
             self.set_tile(w - 3, y, TileType::Empty);
         }
+        self.shafts.insert(w - 3);
 
         // A little scenery along the elevator shaft.
         for i in 0..=3 {
@@ -139,6 +292,40 @@ mod tests {
         assert_eq!(TileType::Void, m.tile(30, 20));
     }
 
+    #[test]
+    fn build_shafts() {
+        let mut m = Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
+        assert!(m.is_shaft(ELEVATOR_SHAFT_X));
+        assert_eq!(MAX_ELEVATOR_DEPTH, m.open_run(ELEVATOR_SHAFT_X));
+
+        // It takes a tunnel dug straight down from the surface.
+        assert_eq!(None, m.build_shaft(10));
+        for y in GRASS_LEVEL..GRASS_LEVEL + 5 {
+            m.set_tile(10, y, TileType::Empty);
+        }
+        m.set_tile(10, GRASS_LEVEL + 2, TileType::Ladder);
+        m.set_tile(10, GRASS_LEVEL + 6, TileType::Empty);
+        assert_eq!(5, m.open_run(10));
+        assert_eq!(Some((5, 1)), m.build_shaft(10));
+        assert!(m.is_shaft(10));
+        assert_eq!(TileType::Empty, m.tile(10, GRASS_LEVEL + 2));
+        assert_eq!(TileType::Dirt, m.tile(10, GRASS_LEVEL + 5));
+        assert_eq!(vec![10, ELEVATOR_SHAFT_X], m.shafts().collect::<Vec<_>>());
+
+        // Not right next to another shaft, or in front of the bank.
+        for x in [12, BANK_X] {
+            for y in GRASS_LEVEL..GRASS_LEVEL + 5 {
+                m.set_tile(x, y, TileType::Empty);
+            }
+            assert_eq!(None, m.build_shaft(x));
+        }
+        // And not too shallow.
+        for y in GRASS_LEVEL..GRASS_LEVEL + 2 {
+            m.set_tile(25, y, TileType::Empty);
+        }
+        assert_eq!(None, m.build_shaft(25));
+    }
+
     #[test]
     fn set_tile_works() {
         let mut m = Map::new(30, 50);
@@ -146,4 +333,124 @@ mod tests {
         m.set_tile(2, 10, TileType::Rock { hardness: 2 });
         assert_eq!(TileType::Rock { hardness: 2 }, m.tile(2, 10));
     }
+
+    #[test]
+    fn dirty_tiles_work() {
+        let mut m = Map::new(30, 50);
+        assert_eq!(30 * 50, m.take_dirty().len());
+        assert!(m.take_dirty().is_empty());
+
+        m.set_tile(2, 10, TileType::Empty);
+        m.set_tile(2, 10, TileType::Ladder);
+        m.set_tile(20, 40, TileType::Water);
+        let mut dirty = m.take_dirty();
+        dirty.sort_unstable();
+        assert_eq!(vec![(2, 10), (20, 40)], dirty);
+        assert!(m.take_dirty().is_empty());
+
+        // Setting a tile to what it already is isn't a change.
+        m.set_tile(2, 10, TileType::Ladder);
+        assert!(m.take_dirty().is_empty());
+    }
+
+    #[test]
+    fn neighbors_work() {
+        let m = Map::new(30, 50);
+        assert_eq!(
+            vec![(5, 4), (4, 5), (6, 5), (5, 6)],
+            m.neighbors(5, 5).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(1, 0), (0, 1)], m.neighbors(0, 0).collect::<Vec<_>>());
+        assert_eq!(
+            vec![(29, 48), (28, 49)],
+            m.neighbors(29, 49).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn rect_works() {
+        let m = Map::new(30, 50);
+        assert_eq!(
+            vec![(1, 2), (2, 2), (1, 3), (2, 3)],
+            m.rect(2, 3, 1, 2).collect::<Vec<_>>()
+        );
+        // Clipped to the map.
+        assert_eq!(
+            vec![(0, 0), (1, 0), (0, 1), (1, 1)],
+            m.rect(-5, -5, 1, 1).collect::<Vec<_>>()
+        );
+        assert_eq!(4, m.rect(28, 48, 40, 60).count());
+        assert_eq!(0, m.rect(40, 60, 50, 70).count());
+    }
+
+    #[test]
+    fn radius_works() {
+        let m = Map::new(30, 50);
+        assert_eq!(vec![(5, 5)], m.radius(5, 5, 0).collect::<Vec<_>>());
+        assert_eq!(5, m.radius(5, 5, 1).count());
+        assert_eq!(13, m.radius(5, 5, 2).count());
+        // A quarter circle (plus the edges) in the corner.
+        assert_eq!(6, m.radius(0, 0, 2).count());
+        assert!(m.radius(10, 10, 4).all(|(x, y)| m.in_bounds(x, y)));
+    }
+
+    #[test]
+    fn flood_fill_works() {
+        let mut m = Map::new(30, 50);
+        for x in 5..=8 {
+            m.set_tile(x, 20, TileType::Water);
+        }
+        m.set_tile(8, 21, TileType::Water);
+        // Diagonal doesn't count.
+        m.set_tile(9, 22, TileType::Water);
+
+        let water = m.flood_fill(5, 20, |t| t == TileType::Water);
+        assert_eq!(vec![(5, 20), (6, 20), (7, 20), (8, 20), (8, 21)], water);
+
+        // The start is left out if it doesn't match, but the fill still spreads from it.
+        let water = m.flood_fill(4, 20, |t| t == TileType::Water);
+        assert_eq!(vec![(5, 20), (6, 20), (7, 20), (8, 20), (8, 21)], water);
+
+        assert!(m.flood_fill(2, 30, |t| t == TileType::Water).is_empty());
+    }
+
+    #[test]
+    fn is_connected_works() {
+        let mut m = Map::new(30, 50);
+        let open = |t| t == TileType::Empty;
+        for y in 10..=20 {
+            m.set_tile(5, y, TileType::Empty);
+        }
+        for x in 5..=15 {
+            m.set_tile(x, 20, TileType::Empty);
+        }
+        assert!(m.is_connected((5, 10), (15, 20), open));
+        assert!(m.is_connected((15, 20), (5, 10), open));
+        assert!(!m.is_connected((5, 10), (16, 20), open));
+        assert!(!m.is_connected((16, 20), (5, 10), open));
+
+        m.set_tile(10, 20, TileType::Dirt);
+        assert!(!m.is_connected((5, 10), (15, 20), open));
+        assert!(m.is_connected((5, 10), (9, 20), open));
+    }
+
+    #[test]
+    fn line_of_sight_works() {
+        let mut m = Map::new(30, 50);
+        let open = |t| t == TileType::Empty;
+        for x in 5..=15 {
+            for y in 10..=15 {
+                m.set_tile(x, y, TileType::Empty);
+            }
+        }
+        assert!(m.line_of_sight((5, 10), (15, 15), open));
+        assert!(m.line_of_sight((15, 15), (5, 10), open));
+        assert!(m.line_of_sight((5, 10), (5, 10), open));
+        // The end points can be solid.
+        assert!(m.line_of_sight((4, 12), (16, 12), open));
+
+        m.set_tile(10, 12, TileType::Rock { hardness: 0 });
+        assert!(!m.line_of_sight((4, 12), (16, 12), open));
+        assert!(m.line_of_sight((4, 11), (16, 11), open));
+    }
 }
@@ -0,0 +1,134 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use core::ops::RangeInclusive;
+use rand::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Mineral {
+    Coal,
+    Copper,
+    Silver,
+    Gold,
+    Ruby,
+    Diamond,
+}
+
+impl Mineral {
+    pub const ALL: [Mineral; 6] = [
+        Mineral::Coal,
+        Mineral::Copper,
+        Mineral::Silver,
+        Mineral::Gold,
+        Mineral::Ruby,
+        Mineral::Diamond,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mineral::Coal => "Coal",
+            Mineral::Copper => "Copper",
+            Mineral::Silver => "Silver",
+            Mineral::Gold => "Gold",
+            Mineral::Ruby => "Ruby",
+            Mineral::Diamond => "Diamond",
+        }
+    }
+
+    // What the bank pays for one piece.
+    pub fn price(&self) -> i32 {
+        match self {
+            Mineral::Coal => 50,
+            Mineral::Copper => 100,
+            Mineral::Silver => 200,
+            Mineral::Gold => 400,
+            Mineral::Ruby => 800,
+            Mineral::Diamond => 1600,
+        }
+    }
+
+    // The depths (rows below the grass) where this mineral can be found.
+    pub fn depth_band(&self) -> RangeInclusive<i32> {
+        match self {
+            Mineral::Coal => 1..=20,
+            Mineral::Copper => 5..=30,
+            Mineral::Silver => 15..=40,
+            Mineral::Gold => 25..=60,
+            Mineral::Ruby => 35..=60,
+            Mineral::Diamond => 45..=60,
+        }
+    }
+
+    // Relative weight when several minerals share a depth. Higher is more common.
+    pub fn rarity(&self) -> u32 {
+        match self {
+            Mineral::Coal => 12,
+            Mineral::Copper => 8,
+            Mineral::Silver => 6,
+            Mineral::Gold => 4,
+            Mineral::Ruby => 2,
+            Mineral::Diamond => 1,
+        }
+    }
+
+    // Picks a mineral that can be found at the given depth, if there is one.
+    pub fn random_at_depth<R: Rng + ?Sized>(depth: i32, rng: &mut R) -> Option<Mineral> {
+        let candidates: Vec<Mineral> = Mineral::ALL
+            .iter()
+            .copied()
+            .filter(|m| m.depth_band().contains(&depth))
+            .collect();
+        candidates
+            .choose_weighted(rng, |m| m.rarity())
+            .ok()
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deeper_is_more_valuable() {
+        for pair in Mineral::ALL.windows(2) {
+            assert!(pair[0].price() < pair[1].price());
+            assert!(pair[0].rarity() > pair[1].rarity());
+            assert!(pair[0].depth_band().start() < pair[1].depth_band().start());
+        }
+    }
+
+    #[test]
+    fn random_respects_depth_bands() {
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(None, Mineral::random_at_depth(0, &mut rng));
+        assert_eq!(None, Mineral::random_at_depth(100, &mut rng));
+        for depth in 1..=60 {
+            for _ in 0..20 {
+                let m = Mineral::random_at_depth(depth, &mut rng).unwrap();
+                assert!(m.depth_band().contains(&depth));
+            }
+        }
+    }
+
+    #[test]
+    fn shallow_minerals_only() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            assert_eq!(Some(Mineral::Coal), Mineral::random_at_depth(2, &mut rng));
+        }
+    }
+}
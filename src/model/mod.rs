@@ -14,6 +14,23 @@
 
 #![warn(clippy::all)]
 
+pub mod animation;
+pub mod bank;
+pub mod dynamite;
 pub mod elevator;
+pub mod energy;
+pub mod hazard;
+pub mod inventory;
+pub mod key_repeat;
 pub mod map;
+pub mod mineral;
+pub mod movement;
+pub mod path;
+pub mod pick;
 pub mod player;
+pub mod position;
+pub mod pump;
+pub mod run;
+pub mod score;
+pub mod shop;
+pub mod stats;
@@ -0,0 +1,188 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::map::{Map, TileType};
+
+// Whether the player can stand in a tile without digging.
+pub fn is_passable(t: TileType) -> bool {
+    matches!(
+        t,
+        TileType::Empty | TileType::Ladder | TileType::Pump | TileType::Sky | TileType::Water
+    )
+}
+
+// Ladders and water can be held on to, with nothing underfoot.
+pub fn is_climbable(t: TileType) -> bool {
+    matches!(t, TileType::Ladder | TileType::Water)
+}
+
+// Whether the player is unsupported, and will drop down a tile. The top of a
+// ladder is something to stand on, and its bottom rung something to hang from.
+pub fn will_fall(map: &Map, x: i32, y: i32) -> bool {
+    matches!(map.tile(x, y + 1), TileType::Empty | TileType::Water) && !is_climbable(map.tile(x, y))
+}
+
+// Going up means climbing, so it takes a ladder. You can't climb up in thin air.
+pub fn can_climb(map: &Map, x: i32, y: i32) -> bool {
+    is_climbable(map.tile(x, y))
+}
+
+// Whether the climbing rules allow a step from (x, y) towards (tx, ty), whatever
+// is in the way. Stepping off sideways or letting go at the bottom of a ladder is
+// always allowed, and then it's up to the player's footing whether they fall.
+pub fn can_step(map: &Map, x: i32, y: i32, tx: i32, ty: i32) -> bool {
+    if will_fall(map, x, y) {
+        return (tx, ty) == (x, y + 1);
+    }
+    ty >= y || can_climb(map, x, y)
+}
+
+// The tiles the player can get to from here in one step, without digging.
+pub fn steps(map: &Map, x: i32, y: i32) -> Vec<(i32, i32)> {
+    let mut steps = Vec::new();
+    if x > 1 {
+        steps.push((x - 1, y));
+    }
+    if x < ELEVATOR_SHAFT_X {
+        steps.push((x + 1, y));
+    }
+    if y > SKY_HEIGHT {
+        steps.push((x, y - 1));
+    }
+    if y < MAP_HEIGHT - 2 {
+        steps.push((x, y + 1));
+    }
+    steps.retain(|&(sx, sy)| can_step(map, x, y, sx, sy) && is_passable(map.tile(sx, sy)));
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_map() -> Map {
+        Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize)
+    }
+
+    #[test]
+    fn walk_on_the_surface() {
+        let m = new_map();
+        assert_eq!(
+            vec![(9, SKY_HEIGHT), (11, SKY_HEIGHT)],
+            steps(&m, 10, SKY_HEIGHT)
+        );
+    }
+
+    #[test]
+    fn fall_through_air() {
+        let mut m = new_map();
+        m.set_tile(10, 20, TileType::Empty);
+        m.set_tile(10, 21, TileType::Empty);
+        assert!(will_fall(&m, 10, 20));
+        assert_eq!(vec![(10, 21)], steps(&m, 10, 20));
+        assert!(!will_fall(&m, 10, 21));
+    }
+
+    #[test]
+    fn climb_ladders_only() {
+        let mut m = new_map();
+        for y in 20..=22 {
+            m.set_tile(10, y, TileType::Empty);
+        }
+        m.set_tile(10, 21, TileType::Ladder);
+        assert!(can_climb(&m, 10, 21));
+        assert_eq!(vec![(10, 20), (10, 22)], steps(&m, 10, 21));
+        // No climbing out of the bottom of the tunnel without a ladder.
+        assert!(!can_climb(&m, 10, 22));
+        assert!(steps(&m, 10, 22).is_empty());
+    }
+
+    #[test]
+    fn stand_on_a_ladder_top() {
+        let mut m = new_map();
+        for y in 19..=21 {
+            m.set_tile(10, y, TileType::Empty);
+        }
+        m.set_tile(10, 21, TileType::Ladder);
+        // Climb off the top rung, and stand on the ladder without falling.
+        assert!(can_step(&m, 10, 21, 10, 20));
+        assert!(!will_fall(&m, 10, 20));
+        // But there's nothing left to climb.
+        assert!(!can_climb(&m, 10, 20));
+        assert_eq!(vec![(10, 21)], steps(&m, 10, 20));
+    }
+
+    #[test]
+    fn hang_from_the_bottom_rung() {
+        let mut m = new_map();
+        for y in 20..=22 {
+            m.set_tile(10, y, TileType::Empty);
+        }
+        m.set_tile(10, 20, TileType::Ladder);
+        assert!(!will_fall(&m, 10, 20));
+        // Letting go drops you.
+        assert_eq!(vec![(10, 21)], steps(&m, 10, 20));
+        assert!(will_fall(&m, 10, 21));
+    }
+
+    #[test]
+    fn step_off_sideways() {
+        let mut m = new_map();
+        for y in 20..=21 {
+            m.set_tile(10, y, TileType::Ladder);
+            m.set_tile(11, y, TileType::Empty);
+        }
+        m.set_tile(9, 20, TileType::Empty);
+        assert_eq!(vec![(9, 20), (11, 20), (10, 21)], steps(&m, 10, 20));
+        // Solid ground on one side, a drop on the other.
+        assert!(!will_fall(&m, 9, 20));
+        assert!(will_fall(&m, 11, 20));
+    }
+
+    #[test]
+    fn no_digging_overhead_without_a_ladder() {
+        let mut m = new_map();
+        m.set_tile(10, 20, TileType::Empty);
+        assert!(!can_step(&m, 10, 20, 10, 19));
+        assert!(can_step(&m, 10, 20, 9, 20));
+        assert!(can_step(&m, 10, 20, 10, 21));
+        m.set_tile(10, 20, TileType::Ladder);
+        assert!(can_step(&m, 10, 20, 10, 19));
+    }
+
+    #[test]
+    fn no_steering_while_falling() {
+        let mut m = new_map();
+        for x in 9..=11 {
+            m.set_tile(x, 20, TileType::Empty);
+        }
+        m.set_tile(10, 21, TileType::Empty);
+        assert!(!can_step(&m, 10, 20, 9, 20));
+        assert!(!can_step(&m, 10, 20, 11, 20));
+        assert!(can_step(&m, 10, 20, 10, 21));
+    }
+
+    #[test]
+    fn swim() {
+        let mut m = new_map();
+        m.set_tile(10, 20, TileType::Water);
+        m.set_tile(10, 21, TileType::Water);
+        assert!(!will_fall(&m, 10, 20));
+        assert_eq!(vec![(10, 21)], steps(&m, 10, 20));
+        assert_eq!(vec![(10, 20)], steps(&m, 10, 21));
+    }
+}
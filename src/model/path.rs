@@ -0,0 +1,225 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::model::map::Map;
+use crate::model::movement;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+// Finds the shortest walk from one tile to another, without any digging.
+// The path doesn't include the starting tile.
+pub fn find_path(map: &Map, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+    find_path_where(
+        map,
+        from,
+        |x, y| (x, y) == to,
+        |x, y| (x - to.0).abs() + (y - to.1).abs(),
+    )
+}
+
+// A* search towards any tile that satisfies is_goal. The heuristic must never
+// overestimate the number of steps left.
+pub fn find_path_where<G, H>(
+    map: &Map,
+    from: (i32, i32),
+    is_goal: G,
+    heuristic: H,
+) -> Option<Vec<(i32, i32)>>
+where
+    G: Fn(i32, i32) -> bool,
+    H: Fn(i32, i32) -> i32,
+{
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::new();
+    let mut cost = HashMap::from([(from, 0)]);
+    let mut open = BinaryHeap::from([Reverse((heuristic(from.0, from.1), from))]);
+
+    while let Some(Reverse((_, (x, y)))) = open.pop() {
+        if is_goal(x, y) {
+            let mut path = VecDeque::new();
+            let mut at = (x, y);
+            while at != from {
+                path.push_front(at);
+                at = came_from[&at];
+            }
+            return Some(path.into());
+        }
+        let next_cost = cost[&(x, y)] + 1;
+        for step in movement::steps(map, x, y) {
+            if next_cost < *cost.get(&step).unwrap_or(&i32::MAX) {
+                cost.insert(step, next_cost);
+                came_from.insert(step, (x, y));
+                open.push(Reverse((next_cost + heuristic(step.0, step.1), step)));
+            }
+        }
+    }
+    None
+}
+
+// Follows a path one step at a time.
+#[derive(Default)]
+pub struct AutoWalk {
+    path: VecDeque<(i32, i32)>,
+}
+
+impl AutoWalk {
+    pub fn start(&mut self, path: Vec<(i32, i32)>) {
+        self.path = path.into();
+    }
+
+    pub fn stop(&mut self) {
+        self.path.clear();
+    }
+
+    pub fn is_walking(&self) -> bool {
+        !self.path.is_empty()
+    }
+
+    // The next tile to move to from where the player is now. The walk stops if
+    // the player has left the path, or the terrain no longer allows the next step.
+    pub fn next_step(&mut self, map: &Map, x: i32, y: i32) -> Option<(i32, i32)> {
+        // Skip ahead to wherever the player is, since falls can happen quickly.
+        if let Some(i) = self.path.iter().position(|&p| p == (x, y)) {
+            self.path.drain(..=i);
+        }
+        match self.path.front() {
+            Some(&next) if movement::steps(map, x, y).contains(&next) => Some(next),
+            _ => {
+                self.stop();
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::model::map::TileType;
+
+    // A tunnel at row 20 from x=5 up to the shaft, and a ladder at x=5 up to the
+    // surface.
+    fn tunnel_map() -> Map {
+        let mut m = Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
+        for x in 5..ELEVATOR_SHAFT_X {
+            m.set_tile(x, 20, TileType::Empty);
+        }
+        for y in GRASS_LEVEL..=20 {
+            m.set_tile(5, y, TileType::Ladder);
+        }
+        m
+    }
+
+    #[test]
+    fn already_there() {
+        let m = tunnel_map();
+        assert_eq!(Some(vec![]), find_path(&m, (10, 20), (10, 20)));
+    }
+
+    #[test]
+    fn along_a_tunnel() {
+        let m = tunnel_map();
+        assert_eq!(
+            Some(vec![(11, 20), (12, 20), (13, 20)]),
+            find_path(&m, (10, 20), (13, 20))
+        );
+    }
+
+    #[test]
+    fn up_the_ladder() {
+        let m = tunnel_map();
+        let path = find_path(&m, (10, 20), (7, SKY_HEIGHT)).unwrap();
+        assert_eq!(5 + (20 - SKY_HEIGHT) + 2, path.len() as i32);
+        assert_eq!(Some(&(7, SKY_HEIGHT)), path.last());
+        assert!(path.contains(&(5, GRASS_LEVEL)));
+    }
+
+    #[test]
+    fn no_climbing_without_a_ladder() {
+        let mut m = tunnel_map();
+        for y in GRASS_LEVEL..20 {
+            m.set_tile(5, y, TileType::Empty);
+        }
+        assert_eq!(None, find_path(&m, (10, 20), (7, SKY_HEIGHT)));
+        // But we can still drop down.
+        assert!(find_path(&m, (5, GRASS_LEVEL), (10, 20)).is_some());
+    }
+
+    #[test]
+    fn falls_are_followed() {
+        let mut m = tunnel_map();
+        // A hole in the floor of the tunnel.
+        m.set_tile(8, 21, TileType::Empty);
+        m.set_tile(8, 22, TileType::Empty);
+        m.set_tile(9, 22, TileType::Empty);
+        // Walking over the hole means falling in, so there's no way across.
+        assert_eq!(None, find_path(&m, (10, 20), (6, 20)));
+        assert_eq!(
+            Some(vec![(8, 20), (8, 21), (8, 22), (9, 22)]),
+            find_path(&m, (9, 20), (9, 22))
+        );
+    }
+
+    #[test]
+    fn goal_predicate() {
+        let m = tunnel_map();
+        let path = find_path_where(
+            &m,
+            (10, 20),
+            |x, _| x == ELEVATOR_SHAFT_X - 1,
+            |x, _| (ELEVATOR_SHAFT_X - 1 - x).abs(),
+        )
+        .unwrap();
+        assert_eq!(Some(&(ELEVATOR_SHAFT_X - 1, 20)), path.last());
+    }
+
+    #[test]
+    fn auto_walk() {
+        let mut m = tunnel_map();
+        let mut walk = AutoWalk::default();
+        walk.start(find_path(&m, (10, 20), (14, 20)).unwrap());
+        assert!(walk.is_walking());
+
+        assert_eq!(Some((11, 20)), walk.next_step(&m, 10, 20));
+        // Still waiting to get there.
+        assert_eq!(Some((11, 20)), walk.next_step(&m, 10, 20));
+        assert_eq!(Some((12, 20)), walk.next_step(&m, 11, 20));
+
+        // A cave-in blocks the way.
+        m.set_tile(13, 20, TileType::Dirt);
+        assert_eq!(None, walk.next_step(&m, 12, 20));
+        assert!(!walk.is_walking());
+    }
+
+    #[test]
+    fn auto_walk_arrives() {
+        let m = tunnel_map();
+        let mut walk = AutoWalk::default();
+        walk.start(find_path(&m, (10, 20), (11, 20)).unwrap());
+        assert_eq!(Some((11, 20)), walk.next_step(&m, 10, 20));
+        assert_eq!(None, walk.next_step(&m, 11, 20));
+        assert!(!walk.is_walking());
+    }
+
+    #[test]
+    fn auto_walk_off_the_path() {
+        let m = tunnel_map();
+        let mut walk = AutoWalk::default();
+        walk.start(find_path(&m, (10, 20), (14, 20)).unwrap());
+        assert_eq!(None, walk.next_step(&m, 5, 10));
+        assert!(!walk.is_walking());
+    }
+}
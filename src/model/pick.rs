@@ -0,0 +1,99 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Pick {
+    Bronze,
+    Iron,
+    Steel,
+    Diamond,
+}
+
+impl Pick {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pick::Bronze => "Bronze",
+            Pick::Iron => "Iron",
+            Pick::Steel => "Steel",
+            Pick::Diamond => "Diamond",
+        }
+    }
+
+    // The hardest rock this pick can break.
+    pub fn max_hardness(&self) -> u8 {
+        match self {
+            Pick::Bronze => 1,
+            Pick::Iron => 2,
+            Pick::Steel => 3,
+            Pick::Diamond => u8::MAX,
+        }
+    }
+
+    // Energy needed to break rock of the given hardness, if it can be broken at all.
+    pub fn energy_cost(&self, hardness: u8) -> Option<i32> {
+        if hardness > self.max_hardness() {
+            return None;
+        }
+        let cost = 2i32.checked_pow(hardness as u32)?.checked_mul(3)?;
+        let cost = match self {
+            Pick::Bronze => cost,
+            Pick::Iron => cost * 3 / 4,
+            Pick::Steel => cost / 2,
+            Pick::Diamond => cost / 4,
+        };
+        Some(cost.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bronze() {
+        assert_eq!(Some(3), Pick::Bronze.energy_cost(0));
+        assert_eq!(Some(6), Pick::Bronze.energy_cost(1));
+        assert_eq!(None, Pick::Bronze.energy_cost(2));
+    }
+
+    #[test]
+    fn better_picks_break_harder_rock() {
+        assert_eq!(Some(9), Pick::Iron.energy_cost(2));
+        assert_eq!(None, Pick::Iron.energy_cost(3));
+        assert_eq!(Some(12), Pick::Steel.energy_cost(3));
+        assert_eq!(None, Pick::Steel.energy_cost(4));
+        assert_eq!(Some(12), Pick::Diamond.energy_cost(4));
+    }
+
+    #[test]
+    fn better_picks_are_cheaper() {
+        let picks = [Pick::Bronze, Pick::Iron, Pick::Steel, Pick::Diamond];
+        for pair in picks.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].max_hardness() < pair[1].max_hardness());
+            for h in 0..=pair[0].max_hardness() {
+                assert!(pair[0].energy_cost(h) >= pair[1].energy_cost(h));
+            }
+        }
+        // Never free.
+        assert_eq!(Some(1), Pick::Diamond.energy_cost(0));
+    }
+
+    #[test]
+    fn absurdly_hard_rock() {
+        assert_eq!(None, Pick::Diamond.energy_cost(u8::MAX));
+    }
+}
@@ -14,32 +14,69 @@
 
 #![warn(clippy::all)]
 
+use crate::constants::*;
+use crate::model::bank::Loan;
+use crate::model::energy;
+use crate::model::inventory::{Inventory, Item};
+use crate::model::mineral::Mineral;
+use crate::model::pick::Pick;
+use crate::model::position::{Position, Target};
+use bevy::prelude::Component;
+
 const INITIAL_MONEY: i32 = 1000;
 
-const MAX_ENERGY: i32 = 100;
+pub const MAX_ENERGY: i32 = 100;
 const INTIAL_ENERGY: i32 = MAX_ENERGY;
-const ENERGY_COST: i32 = 10;
 
+pub const MAX_OXYGEN: i32 = 100;
+
+pub const MAX_HEALTH: i32 = 100;
+// Falls of up to this many tiles are harmless. Each tile beyond that hurts.
+const SAFE_FALL: i32 = 3;
+const FALL_DAMAGE: i32 = 10;
+const HOSPITAL_COST: i32 = 500;
+// What a ladder taken down is worth as scrap, when there's no room to carry it.
+const LADDER_REFUND: i32 = 4;
+
+const MAX_WEIGHT: i32 = 60;
+const MAX_VOLUME: i32 = 30;
+// The longest pause between steps, when fully loaded.
+const MAX_STEP_DELAY: f32 = 0.3;
+
+// A miner's wallet, gear and wellbeing. Where they are is kept in the Position
+// and Target components alongside.
+#[derive(Component)]
 pub struct Player {
-    pub x: i32,
-    pub y: i32,
-    pub target_x: i32,
-    pub target_y: i32,
-    pub rock_hammer: bool,
+    // Set while the player is swinging the pick, to break rock.
+    pub use_pick: bool,
+    pub pick: Pick,
+    pub inventory: Inventory,
+    pub loan: Loan,
+    // Seconds until the player can take another step.
+    pub step_wait: f32,
     money: i32,
     energy: i32,
+    oxygen: i32,
+    health: i32,
+    // How many tiles the player has fallen so far, without landing.
+    fall_height: i32,
+    run_over: bool,
 }
 
 impl Player {
-    pub fn new(x: i32, y: i32) -> Self {
+    pub fn new() -> Self {
         Player {
-            x,
-            y,
-            target_x: x,
-            target_y: y,
-            rock_hammer: false,
+            use_pick: false,
+            pick: Pick::Bronze,
+            inventory: Inventory::new(MAX_WEIGHT, MAX_VOLUME),
+            loan: Loan::default(),
+            step_wait: 0.0,
             money: INITIAL_MONEY,
             energy: INTIAL_ENERGY,
+            oxygen: MAX_OXYGEN,
+            health: MAX_HEALTH,
+            fall_height: 0,
+            run_over: false,
         }
     }
 
@@ -51,20 +88,72 @@ impl Player {
         self.money += m;
     }
 
-    pub fn pay_money(&mut self, m: i32) {
-        self.money -= m;
+    pub fn pay_money(&mut self, m: i32) -> bool {
+        let ok = self.money >= m;
+        if ok {
+            self.money -= m;
+        }
+        ok
+    }
+
+    // Pays, borrowing whatever the player doesn't have, as long as the bank allows it.
+    pub fn pay_on_credit(&mut self, m: i32) -> bool {
+        let shortfall = core::cmp::max(0, m - self.money);
+        let ok = self.loan.borrow(shortfall);
+        if ok {
+            self.money += shortfall;
+            self.money -= m;
+        }
+        ok
+    }
+
+    pub fn borrow(&mut self, m: i32) -> bool {
+        let ok = self.loan.borrow(m);
+        if ok {
+            self.money += m;
+        }
+        ok
+    }
+
+    // Pays back as much of the loan as possible, up to m.
+    pub fn repay(&mut self, m: i32) -> i32 {
+        let paid = self.loan.repay(core::cmp::min(m, self.money));
+        self.money -= paid;
+        paid
+    }
+
+    // Once the debt is past the credit limit, the bank takes everything.
+    pub fn is_bankrupt(&self) -> bool {
+        self.loan.is_over_limit()
     }
 
     pub fn energy(&self) -> i32 {
         self.energy
     }
 
-    pub fn refill_energy(&mut self) {
-        // TODO: This could be done smarter.
-        while self.energy < MAX_ENERGY && self.money >= ENERGY_COST {
-            self.energy += 1;
-            self.money -= ENERGY_COST;
+    // Buys exactly n units of energy, or none at all if they don't fit or can't be paid for.
+    pub fn buy_energy(&mut self, n: i32) -> bool {
+        let ok = n >= 0 && self.energy + n <= MAX_ENERGY && self.pay_money(energy::cost(n));
+        if ok {
+            self.energy += n;
         }
+        ok
+    }
+
+    // Buys as much energy as the budget allows, without going past the maximum.
+    // Returns the number of units bought.
+    pub fn buy_energy_for(&mut self, budget: i32) -> i32 {
+        let n = core::cmp::min(
+            MAX_ENERGY - self.energy,
+            energy::units_for(core::cmp::min(budget, self.money)),
+        );
+        self.buy_energy(n);
+        n
+    }
+
+    // Tops up as far as the money goes.
+    pub fn fill_energy(&mut self) -> i32 {
+        self.buy_energy_for(self.money)
     }
 
     pub fn use_energy(&mut self, e: i32) -> bool {
@@ -74,49 +163,280 @@ impl Player {
         }
         ok
     }
+
+    // Harder rock takes more energy to break, and needs a better pick.
+    pub fn break_rock(&mut self, hardness: u8) -> bool {
+        match self.pick.energy_cost(hardness) {
+            Some(cost) => self.use_energy(cost),
+            None => false,
+        }
+    }
+
+    // Trades the old pick in for a better one.
+    pub fn upgrade_pick(&mut self, pick: Pick, price: i32) -> bool {
+        let ok = pick > self.pick && self.pay_money(price);
+        if ok {
+            self.pick = pick;
+        }
+        ok
+    }
+
+    // Buys n of something, if there is money for it and room to carry it.
+    pub fn buy(&mut self, item: Item, n: i32, price: i32) -> bool {
+        let ok = self.money >= price && self.inventory.add(item, n);
+        if ok {
+            self.money -= price;
+        }
+        ok
+    }
+
+    // Sells everything that has been mined, returning how much it was worth.
+    pub fn sell_ore(&mut self) -> i32 {
+        let earned = self
+            .inventory
+            .take_ore()
+            .iter()
+            .map(|(m, n)| m.price() * n)
+            .sum();
+        self.receive_money(earned);
+        earned
+    }
+
+    // Takes a ladder down to use again, or sells it for scrap if the pack is full.
+    // Returns whether it was kept.
+    pub fn take_ladder(&mut self) -> bool {
+        let kept = self.inventory.add(Item::Ladder, 1);
+        if !kept {
+            self.receive_money(LADDER_REFUND);
+        }
+        kept
+    }
+
+    // Carrying more than half a load slows the player down.
+    pub fn step_delay(&self) -> f32 {
+        f32::max(0.0, self.inventory.load() - 0.5) * 2.0 * MAX_STEP_DELAY
+    }
+
+    // ... and makes every step tiring.
+    pub fn step_energy(&self) -> i32 {
+        match self.inventory.load() {
+            l if l > 0.75 => 2,
+            l if l > 0.5 => 1,
+            _ => 0,
+        }
+    }
+
+    // Too tired for the next step, the player leaves a piece of ore behind, the
+    // heaviest they have. Only ore: the things they paid for are kept.
+    pub fn lighten_load(&mut self) -> Option<Mineral> {
+        if self.energy >= self.step_energy() {
+            return None;
+        }
+        self.inventory.drop_heaviest_ore()
+    }
+
+    pub fn oxygen(&self) -> i32 {
+        self.oxygen
+    }
+
+    pub fn breathe(&mut self, o: i32) {
+        self.oxygen = core::cmp::min(MAX_OXYGEN, self.oxygen + o);
+    }
+
+    // Returns false once the air has run out.
+    pub fn use_oxygen(&mut self, o: i32) -> bool {
+        self.oxygen = core::cmp::max(0, self.oxygen - o);
+        self.oxygen > 0
+    }
+
+    pub fn health(&self) -> i32 {
+        self.health
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health == 0
+    }
+
+    pub fn hurt(&mut self, h: i32) {
+        self.health = core::cmp::max(0, self.health - h);
+    }
+
+    pub fn heal(&mut self, h: i32) {
+        self.health = core::cmp::min(MAX_HEALTH, self.health + h);
+    }
+
+    pub fn fall(&mut self) {
+        self.fall_height += 1;
+    }
+
+    // Long falls hurt, unless there is water to land in.
+    pub fn land(&mut self, in_water: bool) {
+        if !in_water {
+            self.hurt(core::cmp::max(0, self.fall_height - SAFE_FALL) * FALL_DAMAGE);
+        }
+        self.fall_height = 0;
+    }
+
+    // Wakes up in the hospital, back at the start, patched up, but without the ore
+    // that was being carried. Returns the ore, which was dropped where the player
+    // fell. If the hospital bill can't be paid, even on credit, the game is over.
+    pub fn hospitalize(&mut self, pos: &mut Position, target: &mut Target) -> Vec<(Mineral, i32)> {
+        let ore = self.inventory.take_ore();
+        if self.pay_on_credit(HOSPITAL_COST) {
+            self.health = MAX_HEALTH;
+            self.oxygen = MAX_OXYGEN;
+            self.fall_height = 0;
+            self.step_wait = 0.0;
+            *pos = start_position();
+            *target = pos.target();
+        } else {
+            self.end_run();
+        }
+        ore
+    }
+
+    // Retired, or bust. Either way, the score gets recorded and a new game starts.
+    pub fn end_run(&mut self) {
+        self.run_over = true;
+    }
+
+    pub fn is_run_over(&self) -> bool {
+        self.run_over
+    }
+
+    // What the player is worth, once the bank has been paid back.
+    pub fn net_worth(&self) -> i32 {
+        self.money - self.loan.debt()
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Player::new()
+    }
+}
+
+// Where every miner starts out, and where the hospital sends them back to.
+pub fn start_position() -> Position {
+    Position::new(PLAYER_START_X, PLAYER_START_Y)
+}
+
+// Local co-op: a second miner can join in, with their own wallet and energy.
+pub const MAX_PLAYERS: usize = 2;
+
+// Which player this is, counting from zero. It decides the controls and colours.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayerNumber(pub usize);
+
+// The players who could still join in, given the ones already playing.
+pub fn open_slots(joined: &[PlayerNumber]) -> Vec<PlayerNumber> {
+    (0..MAX_PLAYERS)
+        .map(PlayerNumber)
+        .filter(|number| !joined.contains(number))
+        .collect()
+}
+
+// The miners play as a team, so the run ends as soon as either of them is done.
+pub fn is_team_done<'a>(mut players: impl Iterator<Item = &'a Player>) -> bool {
+    players.any(|p| p.is_run_over())
+}
+
+// Their money counts together, too.
+pub fn team_net_worth<'a>(players: impl Iterator<Item = &'a Player>) -> i32 {
+    players.map(|p| p.net_worth()).sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::bank::CREDIT_LIMIT;
 
     #[test]
     fn init_works() {
-        let p = Player::new(3, 4);
-        assert_eq!(3, p.x);
-        assert_eq!(4, p.y);
+        let p = Player::new();
+        let pos = start_position();
+        assert_eq!((PLAYER_START_X, PLAYER_START_Y), (pos.x, pos.y));
+        assert!(pos.is_at(pos.target()));
         assert_eq!(1000, p.money());
         assert_eq!(100, p.energy());
+        assert_eq!(MAX_OXYGEN, p.oxygen());
     }
 
     #[test]
     fn money_works() {
-        let mut p = Player::new(0, 0);
+        let mut p = Player::new();
 
         let m1 = p.money();
         p.receive_money(42);
         let m2 = p.money();
         assert_eq!(m1 + 42, m2);
 
-        p.pay_money(p.money());
+        assert!(p.pay_money(p.money()));
+        assert_eq!(0, p.money());
+
+        assert!(!p.pay_money(1));
+        assert_eq!(0, p.money());
+    }
+
+    #[test]
+    fn loans_work() {
+        let mut p = Player::new();
+        let m1 = p.money();
+
+        assert!(p.borrow(500));
+        assert_eq!(m1 + 500, p.money());
+        assert_eq!(500, p.loan.debt());
+        assert!(!p.borrow(CREDIT_LIMIT));
+
+        assert_eq!(200, p.repay(200));
+        assert_eq!(m1 + 300, p.money());
+        assert_eq!(300, p.repay(1000));
+        assert_eq!(m1, p.money());
+        assert_eq!(0, p.loan.debt());
+
+        // Can't repay with money you don't have.
+        p.borrow(100);
+        p.pay_money(p.money() - 40);
+        assert_eq!(40, p.repay(100));
+        assert_eq!(60, p.loan.debt());
+    }
+
+    #[test]
+    fn paying_on_credit() {
+        let mut p = Player::new();
+        let m1 = p.money();
+        assert!(p.pay_on_credit(100));
+        assert_eq!(m1 - 100, p.money());
+        assert_eq!(0, p.loan.debt());
+
+        assert!(p.pay_on_credit(p.money() + 300));
         assert_eq!(0, p.money());
+        assert_eq!(300, p.loan.debt());
 
-        p.pay_money(1);
-        assert_eq!(-1, p.money());
+        assert!(!p.pay_on_credit(CREDIT_LIMIT));
+        assert_eq!(0, p.money());
+        assert_eq!(300, p.loan.debt());
+        assert!(!p.is_bankrupt());
+
+        assert!(p.pay_on_credit(CREDIT_LIMIT - 300));
+        assert!(!p.is_bankrupt());
+        p.loan.charge_interest();
+        assert!(p.is_bankrupt());
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn energy_works() {
-        let mut p = Player::new(0, 0);
+        let mut p = Player::new();
         p.receive_money(100000);
 
-        p.refill_energy();
+        p.fill_energy();
         assert_eq!(MAX_ENERGY, p.energy());
 
         assert_eq!(true, p.use_energy(p.energy() / 2));
         assert_eq!(MAX_ENERGY / 2, p.energy());
 
-        p.refill_energy();
+        p.fill_energy();
         assert_eq!(MAX_ENERGY, p.energy());
 
         assert_eq!(true, p.use_energy(MAX_ENERGY / 2));
@@ -130,20 +450,337 @@ mod tests {
     }
 
     #[test]
+    fn health_works() {
+        let mut p = Player::new();
+        assert_eq!(MAX_HEALTH, p.health());
+        p.hurt(30);
+        assert_eq!(MAX_HEALTH - 30, p.health());
+        p.heal(10);
+        assert_eq!(MAX_HEALTH - 20, p.health());
+        p.heal(MAX_HEALTH);
+        assert_eq!(MAX_HEALTH, p.health());
+        assert!(!p.is_dead());
+        p.hurt(MAX_HEALTH + 1);
+        assert_eq!(0, p.health());
+        assert!(p.is_dead());
+    }
+
+    #[test]
+    fn falls_hurt() {
+        let mut p = Player::new();
+        for _ in 0..SAFE_FALL {
+            p.fall();
+        }
+        p.land(false);
+        assert_eq!(MAX_HEALTH, p.health());
+
+        for _ in 0..SAFE_FALL + 2 {
+            p.fall();
+        }
+        p.land(false);
+        assert_eq!(MAX_HEALTH - 2 * FALL_DAMAGE, p.health());
+
+        // Water breaks the fall.
+        for _ in 0..SAFE_FALL + 2 {
+            p.fall();
+        }
+        p.land(true);
+        assert_eq!(MAX_HEALTH - 2 * FALL_DAMAGE, p.health());
+    }
+
+    #[test]
+    fn hospital() {
+        let mut p = Player::new();
+        p.inventory.add(Item::Ore(Mineral::Gold), 2);
+        p.inventory.add(Item::Ladder, 3);
+        p.hurt(MAX_HEALTH);
+        p.use_oxygen(MAX_OXYGEN);
+        let m1 = p.money();
+        let mut pos = Position::new(10, 20);
+        let mut target = Target { x: 11, y: 20 };
+
+        assert_eq!(
+            vec![(Mineral::Gold, 2)],
+            p.hospitalize(&mut pos, &mut target)
+        );
+        assert_eq!(start_position(), pos);
+        assert_eq!(start_position().target(), target);
+        assert_eq!(MAX_HEALTH, p.health());
+        assert_eq!(MAX_OXYGEN, p.oxygen());
+        assert_eq!(m1 - HOSPITAL_COST, p.money());
+        assert_eq!(3, p.inventory.count(Item::Ladder));
+    }
+
+    #[test]
+    fn hospital_on_credit() {
+        let mut p = Player::new();
+        p.pay_money(p.money() - 100);
+        p.hurt(MAX_HEALTH);
+        p.hospitalize(&mut Position::new(10, 20), &mut Target::default());
+        assert_eq!(0, p.money());
+        assert_eq!(HOSPITAL_COST - 100, p.loan.debt());
+        assert_eq!(MAX_HEALTH, p.health());
+    }
+
+    #[test]
+    fn hospital_too_expensive() {
+        let mut p = Player::new();
+        assert!(p.borrow(CREDIT_LIMIT));
+        p.pay_money(p.money() - HOSPITAL_COST + 1);
+        p.inventory.add(Item::Ore(Mineral::Coal), 3);
+        p.hurt(MAX_HEALTH);
+        assert!(!p.is_run_over());
+        let mut pos = Position::new(10, 20);
+        let mut target = pos.target();
+        assert_eq!(
+            vec![(Mineral::Coal, 3)],
+            p.hospitalize(&mut pos, &mut target)
+        );
+        // Left where they fell.
+        assert_eq!(Position::new(10, 20), pos);
+        assert!(p.is_run_over());
+        assert!(p.is_dead());
+        assert_eq!(HOSPITAL_COST - 1, p.money());
+    }
+
+    #[test]
+    fn net_worth() {
+        let mut p = Player::new();
+        assert_eq!(INITIAL_MONEY, p.net_worth());
+        p.borrow(300);
+        assert_eq!(INITIAL_MONEY, p.net_worth());
+        p.pay_money(p.money());
+        assert_eq!(-300, p.net_worth());
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn energy_costs_money() {
-        let mut p = Player::new(0, 0);
+        let mut p = Player::new();
 
         let m1 = p.money();
         assert_eq!(true, p.use_energy(p.energy()));
         assert_eq!(m1, p.money());
-        p.refill_energy();
-        assert_eq!(m1 - p.energy() * ENERGY_COST, p.money());
+        assert_eq!(MAX_ENERGY, p.fill_energy());
+        assert_eq!(m1 - energy::cost(MAX_ENERGY), p.money());
 
         p.pay_money(p.money());
         assert_eq!(true, p.use_energy(p.energy()));
-        p.receive_money(2 * ENERGY_COST);
-        p.refill_energy();
+        p.receive_money(energy::cost(2) + 1);
+        assert_eq!(2, p.fill_energy());
         assert_eq!(2, p.energy());
-        assert_eq!(0, p.money());
+        assert_eq!(1, p.money());
+    }
+
+    #[test]
+    fn buying_exact_energy() {
+        let mut p = Player::new();
+        let m1 = p.money();
+
+        // Already full.
+        assert!(!p.buy_energy(1));
+        assert!(p.buy_energy(0));
+        assert_eq!(m1, p.money());
+
+        p.use_energy(30);
+        assert!(!p.buy_energy(31));
+        assert!(!p.buy_energy(-1));
+        assert!(p.buy_energy(30));
+        assert_eq!(MAX_ENERGY, p.energy());
+        assert_eq!(m1 - energy::cost(30), p.money());
+
+        // Can't afford it.
+        p.use_energy(30);
+        p.pay_money(p.money() - energy::cost(30) + 1);
+        assert!(!p.buy_energy(30));
+        assert_eq!(MAX_ENERGY - 30, p.energy());
+        assert!(p.buy_energy(29));
+    }
+
+    #[test]
+    fn buying_energy_on_a_budget() {
+        let mut p = Player::new();
+        p.use_energy(MAX_ENERGY);
+        let m1 = p.money();
+
+        assert_eq!(0, p.buy_energy_for(energy::cost(1) - 1));
+        assert_eq!(m1, p.money());
+        assert_eq!(3, p.buy_energy_for(energy::cost(3) + 1));
+        assert_eq!(m1 - energy::cost(3), p.money());
+        assert_eq!(3, p.energy());
+
+        // No more than there is room for.
+        let m2 = p.money();
+        assert_eq!(MAX_ENERGY - 3, p.buy_energy_for(100000));
+        assert_eq!(MAX_ENERGY, p.energy());
+        assert_eq!(m2 - energy::cost(MAX_ENERGY - 3), p.money());
+
+        // No more than there is money for.
+        p.use_energy(MAX_ENERGY);
+        p.pay_money(p.money() - 55);
+        assert_eq!(5, p.buy_energy_for(1000));
+        assert_eq!(5, p.money());
+    }
+
+    #[test]
+    fn oxygen_works() {
+        let mut p = Player::new();
+
+        assert!(p.use_oxygen(MAX_OXYGEN - 10));
+        assert_eq!(10, p.oxygen());
+
+        p.breathe(5);
+        assert_eq!(15, p.oxygen());
+        p.breathe(MAX_OXYGEN);
+        assert_eq!(MAX_OXYGEN, p.oxygen());
+
+        assert!(!p.use_oxygen(MAX_OXYGEN + 1));
+        assert_eq!(0, p.oxygen());
+        assert!(!p.use_oxygen(1));
+        assert_eq!(0, p.oxygen());
+    }
+
+    #[test]
+    fn buying_works() {
+        let mut p = Player::new();
+        assert!(p.buy(Item::Pump, 1, p.money() - 10));
+        assert_eq!(1, p.inventory.count(Item::Pump));
+        assert_eq!(10, p.money());
+        assert!(!p.buy(Item::Pump, 1, 11));
+        assert_eq!(1, p.inventory.count(Item::Pump));
+        assert_eq!(10, p.money());
+
+        // No room for any more.
+        p.receive_money(100000);
+        while p.buy(Item::Pump, 1, 1) {}
+        assert!(!p.inventory.can_add(Item::Pump, 1));
+    }
+
+    #[test]
+    fn selling_works() {
+        let mut p = Player::new();
+        p.inventory.add(Item::Ore(Mineral::Gold), 2);
+        p.inventory.add(Item::Ore(Mineral::Coal), 1);
+        p.inventory.add(Item::Pump, 1);
+
+        let m1 = p.money();
+        let earned = 2 * Mineral::Gold.price() + Mineral::Coal.price();
+        assert_eq!(earned, p.sell_ore());
+        assert_eq!(m1 + earned, p.money());
+        assert_eq!(0, p.sell_ore());
+        assert_eq!(1, p.inventory.count(Item::Pump));
+    }
+
+    #[test]
+    fn taking_ladders() {
+        let mut p = Player::new();
+        let m1 = p.money();
+        assert!(p.take_ladder());
+        assert_eq!(1, p.inventory.count(Item::Ladder));
+        assert_eq!(m1, p.money());
+
+        // No room for it, so it's sold.
+        while p.inventory.add(Item::Dynamite, 1) {}
+        assert!(!p.take_ladder());
+        assert_eq!(1, p.inventory.count(Item::Ladder));
+        assert_eq!(m1 + LADDER_REFUND, p.money());
+    }
+
+    #[test]
+    fn heavy_loads_are_slow() {
+        let mut p = Player::new();
+        assert_eq!(0.0, p.step_delay());
+        assert_eq!(0, p.step_energy());
+
+        p.inventory.add(Item::Ore(Mineral::Gold), 3);
+        assert_eq!(0.0, p.step_delay());
+        assert_eq!(0, p.step_energy());
+
+        // Over half of the weight limit.
+        p.inventory.add(Item::Ore(Mineral::Gold), 1);
+        assert!(p.step_delay() > 0.0);
+        assert_eq!(1, p.step_energy());
+
+        // Full.
+        p.inventory.add(Item::Ore(Mineral::Gold), 3);
+        p.inventory.add(Item::Ore(Mineral::Ruby), 4);
+        assert_eq!(MAX_STEP_DELAY, p.step_delay());
+        assert_eq!(2, p.step_energy());
+    }
+
+    #[test]
+    fn lightening_the_load() {
+        let mut p = Player::new();
+        p.inventory.add(Item::Pump, 3);
+        p.inventory.add(Item::Dynamite, 5);
+        p.inventory.add(Item::Ore(Mineral::Coal), 2);
+        p.inventory.add(Item::Ore(Mineral::Gold), 1);
+        assert_eq!(2, p.step_energy());
+        // Not tired yet.
+        assert_eq!(None, p.lighten_load());
+
+        p.use_energy(p.energy());
+        assert_eq!(Some(Mineral::Gold), p.lighten_load());
+        assert_eq!(Some(Mineral::Coal), p.lighten_load());
+        assert_eq!(Some(Mineral::Coal), p.lighten_load());
+        // Still too heavy, but what's left was paid for.
+        assert!(p.step_energy() > 0);
+        assert_eq!(None, p.lighten_load());
+        assert_eq!(3, p.inventory.count(Item::Pump));
+        assert_eq!(5, p.inventory.count(Item::Dynamite));
+    }
+
+    #[test]
+    fn picks_work() {
+        let mut p = Player::new();
+        assert_eq!(Pick::Bronze, p.pick);
+        assert!(p.break_rock(1));
+        assert_eq!(MAX_ENERGY - 6, p.energy());
+        assert!(!p.break_rock(2));
+        assert_eq!(MAX_ENERGY - 6, p.energy());
+
+        let m1 = p.money();
+        assert!(p.upgrade_pick(Pick::Steel, 500));
+        assert_eq!(m1 - 500, p.money());
+        assert!(p.break_rock(2));
+        assert_eq!(MAX_ENERGY - 12, p.energy());
+
+        // No going backwards.
+        assert!(!p.upgrade_pick(Pick::Iron, 10));
+        assert!(!p.upgrade_pick(Pick::Steel, 10));
+        assert_eq!(Pick::Steel, p.pick);
+        assert!(!p.upgrade_pick(Pick::Diamond, p.money() + 1));
+        assert_eq!(Pick::Steel, p.pick);
+    }
+
+    #[test]
+    fn joining_in() {
+        assert_eq!(vec![PlayerNumber(1)], open_slots(&[PlayerNumber(0)]));
+        assert_eq!(vec![PlayerNumber(0)], open_slots(&[PlayerNumber(1)]));
+        assert_eq!(MAX_PLAYERS, open_slots(&[]).len());
+        assert!(open_slots(&[PlayerNumber(0), PlayerNumber(1)]).is_empty());
+    }
+
+    #[test]
+    fn separate_wallets() {
+        let mut ps = [Player::new(), Player::new()];
+        ps[0].receive_money(500);
+        ps[1].use_energy(30);
+        assert_eq!(1500, ps[0].money());
+        assert_eq!(1000, ps[1].money());
+        assert_eq!(100, ps[0].energy());
+        assert_eq!(70, ps[1].energy());
+
+        ps[1].borrow(200);
+        assert_eq!(1500 + 1000, team_net_worth(ps.iter()));
+    }
+
+    #[test]
+    fn ending_together() {
+        let mut ps = [Player::new(), Player::new()];
+        ps[0].receive_money(500);
+        assert!(!is_team_done(ps.iter()));
+        ps[1].end_run();
+        assert!(is_team_done(ps.iter()));
     }
 }
@@ -0,0 +1,93 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use bevy::prelude::Component;
+
+// Where something is on the map, in tiles.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+// Where something is headed. It's the same as the position while standing still.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Target {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32) -> Self {
+        Position { x, y }
+    }
+
+    // Standing still, right here.
+    pub fn target(&self) -> Target {
+        Target {
+            x: self.x,
+            y: self.y,
+        }
+    }
+
+    pub fn is_at(&self, target: Target) -> bool {
+        (self.x, self.y) == (target.x, target.y)
+    }
+
+    // Moves all the way to the target.
+    pub fn arrive(&mut self, target: Target) {
+        self.x = target.x;
+        self.y = target.y;
+    }
+}
+
+// The smallest (left, top, right, bottom) box around the given positions.
+pub fn bounds(positions: impl Iterator<Item = Position>) -> (i32, i32, i32, i32) {
+    let mut bounds = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+    for p in positions {
+        bounds = (
+            bounds.0.min(p.x),
+            bounds.1.min(p.y),
+            bounds.2.max(p.x),
+            bounds.3.max(p.y),
+        );
+    }
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving() {
+        let mut p = Position::new(3, 4);
+        let t = p.target();
+        assert!(p.is_at(t));
+        let t = Target { x: 4, ..t };
+        assert!(!p.is_at(t));
+        p.arrive(t);
+        assert_eq!(Position::new(4, 4), p);
+    }
+
+    #[test]
+    fn bounds_works() {
+        let ps = [Position::new(3, 4)];
+        assert_eq!((3, 4, 3, 4), bounds(ps.into_iter()));
+        let ps = [Position::new(3, 4), Position::new(10, 2)];
+        assert_eq!((3, 2, 10, 4), bounds(ps.into_iter()));
+    }
+}
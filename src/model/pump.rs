@@ -0,0 +1,266 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::map::{Map, TileType};
+use std::collections::{HashSet, VecDeque};
+
+pub struct Pump {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Pump {
+    pub fn new(x: i32, y: i32) -> Self {
+        Pump { x, y }
+    }
+
+    // The length of hose needed to reach an elevator shaft through open or flooded
+    // tunnels. The water is pumped along it and then up the shaft to the surface.
+    pub fn hose_length(&self, map: &Map) -> Option<i32> {
+        let mut seen = HashSet::from([(self.x, self.y)]);
+        let mut queue = VecDeque::from([(self.x, self.y, 0)]);
+        while let Some((x, y, dist)) = queue.pop_front() {
+            if map.is_shaft(x) {
+                return Some(dist);
+            }
+            for (nx, ny) in map.neighbors(x, y) {
+                if matches!(
+                    map.tile(nx, ny),
+                    TileType::Empty | TileType::Ladder | TileType::Pump | TileType::Water
+                ) && seen.insert((nx, ny))
+                {
+                    queue.push_back((nx, ny, dist + 1));
+                }
+            }
+        }
+        None
+    }
+
+    // Energy needed to get one tile of water out of the mine.
+    pub fn energy_cost(&self, map: &Map) -> Option<i32> {
+        self.hose_length(map)
+            .map(|len| 1 + (len + self.y - GRASS_LEVEL) / 10)
+    }
+
+    // Pumps the water tile farthest from the pump, out of the water touching it, up
+    // the shaft. Taking the farthest one first keeps the rest of the water connected
+    // to the pump. Without a hose to a shaft, the water has nowhere to go.
+    pub fn drain(&self, map: &mut Map) -> Option<(i32, i32)> {
+        self.hose_length(map)?;
+        let water = map.flood_fill(self.x, self.y, |t| t == TileType::Water);
+        let farthest = water.last().copied();
+        if let Some((x, y)) = farthest {
+            map.set_tile(x, y, TileType::Empty);
+        }
+        farthest
+    }
+}
+
+#[derive(Default)]
+pub struct Pumps {
+    pumps: Vec<Pump>,
+}
+
+impl Pumps {
+    pub fn add(&mut self, pump: Pump) {
+        self.pumps.push(pump);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Pump> {
+        self.pumps.iter()
+    }
+
+    // Forgets the pumps that have been blown up or buried.
+    pub fn remove_lost(&mut self, map: &Map) {
+        self.pumps.retain(|p| map.tile(p.x, p.y) == TileType::Pump);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A map with a tunnel at row y running from x to the elevator shaft.
+    fn tunnel_map(x: i32, y: i32) -> Map {
+        let mut m = Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
+        for tx in x..ELEVATOR_SHAFT_X {
+            m.set_tile(tx, y, TileType::Empty);
+        }
+        m
+    }
+
+    fn drain_all(p: &Pump, m: &mut Map) -> Vec<(i32, i32)> {
+        let mut drained = Vec::new();
+        while let Some(t) = p.drain(m) {
+            drained.push(t);
+        }
+        drained
+    }
+
+    #[test]
+    fn no_water() {
+        let mut m = tunnel_map(10, 20);
+        let p = Pump::new(10, 20);
+        assert_eq!(None, p.drain(&mut m));
+    }
+
+    #[test]
+    fn single_tile() {
+        let mut m = tunnel_map(10, 20);
+        m.set_tile(9, 20, TileType::Water);
+        let p = Pump::new(10, 20);
+        assert_eq!(Some((9, 20)), p.drain(&mut m));
+        assert_eq!(TileType::Empty, m.tile(9, 20));
+        assert_eq!(None, p.drain(&mut m));
+    }
+
+    #[test]
+    fn farthest_first() {
+        let mut m = tunnel_map(10, 20);
+        // A vertical column of water below the pump.
+        for y in 21..=24 {
+            m.set_tile(10, y, TileType::Water);
+        }
+        let p = Pump::new(10, 20);
+        assert_eq!(
+            vec![(10, 24), (10, 23), (10, 22), (10, 21)],
+            drain_all(&p, &mut m)
+        );
+    }
+
+    #[test]
+    fn l_shape() {
+        let mut m = tunnel_map(10, 20);
+        m.set_tile(9, 20, TileType::Water);
+        m.set_tile(8, 20, TileType::Water);
+        m.set_tile(8, 21, TileType::Water);
+        m.set_tile(8, 22, TileType::Water);
+        let p = Pump::new(10, 20);
+        assert_eq!(
+            vec![(8, 22), (8, 21), (8, 20), (9, 20)],
+            drain_all(&p, &mut m)
+        );
+    }
+
+    #[test]
+    fn pool_drains_completely() {
+        let mut m = tunnel_map(10, 20);
+        for x in 5..=9 {
+            for y in 18..=23 {
+                m.set_tile(x, y, TileType::Water);
+            }
+        }
+        let p = Pump::new(10, 20);
+        assert_eq!(30, drain_all(&p, &mut m).len());
+        for x in 5..=9 {
+            for y in 18..=23 {
+                assert_eq!(TileType::Empty, m.tile(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn disconnected_water_stays() {
+        let mut m = tunnel_map(10, 20);
+        m.set_tile(9, 20, TileType::Water);
+        // Only touches diagonally.
+        m.set_tile(8, 21, TileType::Water);
+        // Separated by dirt.
+        m.set_tile(10, 22, TileType::Water);
+        let p = Pump::new(10, 20);
+        assert_eq!(vec![(9, 20)], drain_all(&p, &mut m));
+        assert_eq!(TileType::Water, m.tile(8, 21));
+        assert_eq!(TileType::Water, m.tile(10, 22));
+    }
+
+    #[test]
+    fn water_behind_rock_stays() {
+        let mut m = tunnel_map(10, 20);
+        m.set_tile(9, 20, TileType::Rock { hardness: 1 });
+        m.set_tile(8, 20, TileType::Water);
+        let p = Pump::new(10, 20);
+        assert_eq!(None, p.drain(&mut m));
+        assert_eq!(TileType::Water, m.tile(8, 20));
+    }
+
+    #[test]
+    fn hose_reaches_shaft() {
+        let m = tunnel_map(10, 20);
+        let p = Pump::new(10, 20);
+        assert_eq!(Some(ELEVATOR_SHAFT_X - 10), p.hose_length(&m));
+        assert_eq!(
+            Some(1 + (ELEVATOR_SHAFT_X - 10 + 20 - GRASS_LEVEL) / 10),
+            p.energy_cost(&m)
+        );
+    }
+
+    #[test]
+    fn hose_blocked() {
+        let mut m = tunnel_map(10, 20);
+        m.set_tile(15, 20, TileType::Dirt);
+        m.set_tile(9, 20, TileType::Water);
+        let p = Pump::new(10, 20);
+        assert_eq!(None, p.hose_length(&m));
+        assert_eq!(None, p.energy_cost(&m));
+        assert_eq!(None, p.drain(&mut m));
+        assert_eq!(TileType::Water, m.tile(9, 20));
+    }
+
+    #[test]
+    fn hose_reaches_built_shaft() {
+        let mut m = tunnel_map(10, 20);
+        for y in GRASS_LEVEL..=20 {
+            m.set_tile(5, y, TileType::Empty);
+        }
+        for x in 5..10 {
+            m.set_tile(x, 20, TileType::Empty);
+        }
+        let p = Pump::new(10, 20);
+        assert_eq!(Some(ELEVATOR_SHAFT_X - 10), p.hose_length(&m));
+        assert!(m.build_shaft(5).is_some());
+        assert_eq!(Some(5), p.hose_length(&m));
+    }
+
+    #[test]
+    fn hose_through_water() {
+        let mut m = tunnel_map(10, 20);
+        for x in 11..=15 {
+            m.set_tile(x, 20, TileType::Water);
+        }
+        let p = Pump::new(10, 20);
+        assert_eq!(Some(ELEVATOR_SHAFT_X - 10), p.hose_length(&m));
+        assert_eq!(5, drain_all(&p, &mut m).len());
+        assert_eq!(TileType::Empty, m.tile(11, 20));
+    }
+
+    #[test]
+    fn lost_pumps() {
+        let mut m = tunnel_map(10, 20);
+        m.set_tile(10, 20, TileType::Pump);
+        m.set_tile(12, 20, TileType::Pump);
+        let mut pumps = Pumps::default();
+        pumps.add(Pump::new(10, 20));
+        pumps.add(Pump::new(12, 20));
+        pumps.remove_lost(&m);
+        assert_eq!(2, pumps.iter().count());
+
+        m.set_tile(12, 20, TileType::Dirt);
+        pumps.remove_lost(&m);
+        let left: Vec<_> = pumps.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(vec![(10, 20)], left);
+    }
+}
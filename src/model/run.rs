@@ -0,0 +1,204 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::model::score::{Difficulty, Score};
+use rand::prelude::*;
+
+// Which of the run's generators a key is for.
+const TILES: u8 = 0;
+const BREAKDOWNS: u8 = 1;
+
+// A generator of its own for the seed, a place and what it's for.
+fn keyed_rng(seed: u64, x: i32, y: i32, stream: u8) -> StdRng {
+    let mut key = [0; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    key[8..12].copy_from_slice(&x.to_le_bytes());
+    key[12..16].copy_from_slice(&y.to_le_bytes());
+    key[16] = stream;
+    StdRng::from_seed(key)
+}
+
+// One game, from the start until the player retires or goes bust.
+pub struct Run {
+    pub name: String,
+    pub difficulty: Difficulty,
+    pub seed: u64,
+    // The luck of the run: whether trouble is spotted, how far a flood spreads and
+    // so on. What is in the ground comes from tile_rng() instead.
+    pub rng: StdRng,
+    // Breakdowns have luck of their own, so running the elevators doesn't change
+    // what happens in the mine.
+    pub breakdown_rng: StdRng,
+    pub seconds: f32,
+    // Rows below the grass.
+    pub deepest: i32,
+}
+
+impl Run {
+    pub fn new(name: &str, difficulty: Difficulty, seed: u64) -> Self {
+        Run {
+            name: name.to_string(),
+            difficulty,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            breakdown_rng: keyed_rng(seed, 0, 0, BREAKDOWNS),
+            seconds: 0.0,
+            deepest: 0,
+        }
+    }
+
+    // Reads "--name", "--difficulty" and "--seed" options. Without a seed, a random
+    // one is picked.
+    pub fn from_args(args: &[String], default_name: &str) -> Result<Self, String> {
+        let mut name = default_name.to_string();
+        let mut difficulty = Difficulty::Normal;
+        let mut seed = random();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = args.next().ok_or(format!("Missing value for {}", arg))?;
+            match arg.as_str() {
+                "--name" if Score::is_valid_name(value) => name = value.clone(),
+                "--name" => return Err(format!("Bad name {:?}", value)),
+                "--difficulty" => {
+                    difficulty = Difficulty::from_name(value)
+                        .ok_or(format!("Unknown difficulty {}", value))?
+                }
+                "--seed" => seed = value.parse().map_err(|e| format!("Bad seed: {}", e))?,
+                _ => return Err(format!("Unknown option {}", arg)),
+            }
+        }
+        Ok(Run::new(&name, difficulty, seed))
+    }
+
+    // Another go for the same player, with a new seed.
+    pub fn next(&self) -> Self {
+        Run::new(&self.name, self.difficulty, random())
+    }
+
+    // What is buried in each tile depends only on the seed and where the tile is,
+    // so the seed gives the same mine whatever order it's dug in.
+    pub fn tile_rng(&self, x: i32, y: i32) -> StdRng {
+        keyed_rng(self.seed, x, y, TILES)
+    }
+
+    pub fn reached(&mut self, depth: i32) {
+        self.deepest = core::cmp::max(self.deepest, depth);
+    }
+
+    pub fn score(&self, money: i32) -> Score {
+        Score {
+            name: self.name.clone(),
+            money: core::cmp::max(0, money),
+            depth: self.deepest,
+            seconds: self.seconds as i32,
+            seed: self.seed,
+            difficulty: self.difficulty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn defaults() {
+        let run = Run::from_args(&[], "miner").unwrap();
+        assert_eq!("miner", run.name);
+        assert_eq!(Difficulty::Normal, run.difficulty);
+    }
+
+    #[test]
+    fn options() {
+        let run = Run::from_args(&args("--seed 7 --difficulty hard --name Zoe"), "x").unwrap();
+        assert_eq!("Zoe", run.name);
+        assert_eq!(Difficulty::Hard, run.difficulty);
+        assert_eq!(7, run.seed);
+    }
+
+    #[test]
+    fn bad_options() {
+        assert!(Run::from_args(&args("--seed"), "x").is_err());
+        assert!(Run::from_args(&args("--seed abc"), "x").is_err());
+        assert!(Run::from_args(&args("--difficulty nightmare"), "x").is_err());
+        assert!(Run::from_args(&args("--colour blue"), "x").is_err());
+        assert!(Run::from_args(&["--name".to_string(), " ".to_string()], "x").is_err());
+    }
+
+    #[test]
+    fn same_seed_same_luck() {
+        let mut a = Run::new("a", Difficulty::Normal, 1234);
+        let mut b = Run::new("b", Difficulty::Hard, 1234);
+        let rolls: Vec<i32> = (0..20).map(|_| a.rng.gen_range(0..50)).collect();
+        assert_eq!(
+            rolls,
+            (0..20).map(|_| b.rng.gen_range(0..50)).collect::<Vec<_>>()
+        );
+        let rolls: Vec<i32> = (0..20).map(|_| a.breakdown_rng.gen_range(0..50)).collect();
+        assert_eq!(
+            rolls,
+            (0..20)
+                .map(|_| b.breakdown_rng.gen_range(0..50))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn same_seed_same_mine() {
+        let mut a = Run::new("a", Difficulty::Normal, 1234);
+        let b = Run::new("b", Difficulty::Normal, 1234);
+        // Luck used up elsewhere doesn't change what's in the ground.
+        a.rng.gen_range(0..50);
+        let tiles = [(3, 20), (4, 20), (3, 21)];
+        let rolls = |run: &Run| -> Vec<i32> {
+            tiles
+                .iter()
+                .map(|&(x, y)| run.tile_rng(x, y).gen_range(0..1000))
+                .collect()
+        };
+        assert_eq!(rolls(&a), rolls(&b));
+        // But the tiles differ from each other, and from other seeds.
+        let first = rolls(&a);
+        assert!(first[1..].iter().any(|&r| r != first[0]));
+        assert_ne!(first, rolls(&Run::new("c", Difficulty::Normal, 1235)));
+    }
+
+    #[test]
+    fn scoring() {
+        let mut run = Run::new("Zoe", Difficulty::Easy, 99);
+        run.reached(12);
+        run.reached(5);
+        run.seconds = 61.7;
+        let score = run.score(1500);
+        assert_eq!("Zoe", score.name);
+        assert_eq!(1500, score.money);
+        assert_eq!(12, score.depth);
+        assert_eq!(61, score.seconds);
+        assert_eq!(99, score.seed);
+        assert_eq!(Difficulty::Easy, score.difficulty);
+        // Debt doesn't make for a negative score.
+        assert_eq!(0, run.score(-100).money);
+
+        let next = run.next();
+        assert_eq!("Zoe", next.name);
+        assert_eq!(Difficulty::Easy, next.difficulty);
+        assert_eq!(0, next.deepest);
+    }
+}
@@ -0,0 +1,385 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+
+// Only the best scores are kept, by each measure.
+const MAX_SCORES: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    // What digging into dirt turns up, for a roll below DIG_ROLL. The rougher the
+    // going, the more rock and trouble there is, but treasure is just as common.
+    pub fn find(&self, roll: i32) -> Find {
+        let (rock, trouble) = match self {
+            Difficulty::Easy => (12, 1),
+            Difficulty::Normal => (20, 2),
+            Difficulty::Hard => (28, 3),
+        };
+        match roll - rock {
+            r if r < 0 => Find::Rock,
+            r if r < TREASURE_ODDS => Find::Treasure,
+            r if r < TREASURE_ODDS + trouble => Find::Flood,
+            r if r < TREASURE_ODDS + 2 * trouble => Find::CaveIn,
+            _ => Find::Nothing,
+        }
+    }
+}
+
+// Rolls for what's in the dirt are out of this many.
+pub const DIG_ROLL: i32 = 100;
+// Out of DIG_ROLL, whatever the difficulty.
+const TREASURE_ODDS: i32 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Find {
+    Nothing,
+    Rock,
+    Treasure,
+    Flood,
+    CaveIn,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Score {
+    pub name: String,
+    pub money: i32,
+    // Rows below the grass.
+    pub depth: i32,
+    pub seconds: i32,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+}
+
+impl Score {
+    // Names are saved at the end of the line, so anything will do as long as it
+    // isn't blank and stays on the one line.
+    pub fn is_valid_name(name: &str) -> bool {
+        !name.trim().is_empty() && !name.contains(char::is_control)
+    }
+
+    // The name is the rest of the line, kept exactly as it was, spaces and all.
+    fn parse(line: &str) -> Option<Score> {
+        let words: Vec<&str> = line.splitn(6, ' ').collect();
+        if words.len() < 6 || !Score::is_valid_name(words[5]) {
+            return None;
+        }
+        Some(Score {
+            money: words[0].parse().ok()?,
+            depth: words[1].parse().ok()?,
+            seconds: words[2].parse().ok()?,
+            seed: words[3].parse().ok()?,
+            difficulty: Difficulty::from_name(words[4])?,
+            name: words[5].to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    Money,
+    Depth,
+    Time,
+    Name,
+}
+
+impl SortBy {
+    pub fn next(&self) -> SortBy {
+        match self {
+            SortBy::Money => SortBy::Depth,
+            SortBy::Depth => SortBy::Time,
+            SortBy::Time => SortBy::Name,
+            SortBy::Name => SortBy::Money,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortBy::Money => "money",
+            SortBy::Depth => "depth",
+            SortBy::Time => "time played",
+            SortBy::Name => "name",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HighScores {
+    scores: Vec<Score>,
+}
+
+impl HighScores {
+    pub fn add(&mut self, score: Score) {
+        self.scores.push(score);
+        // A record by any measure stays, even if it didn't make much money.
+        let keep: HashSet<usize> = [SortBy::Money, SortBy::Depth, SortBy::Time]
+            .iter()
+            .flat_map(|&by| self.order(by).into_iter().take(MAX_SCORES))
+            .collect();
+        self.scores = std::mem::take(&mut self.scores)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| keep.contains(i))
+            .map(|(_, s)| s)
+            .collect();
+    }
+
+    // Best first, by the given measure. Ties go to the richer player.
+    pub fn sorted(&self, by: SortBy) -> Vec<&Score> {
+        self.order(by)
+            .into_iter()
+            .map(|i| &self.scores[i])
+            .collect()
+    }
+
+    // The same, as indexes into the scores.
+    fn order(&self, by: SortBy) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.scores.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.scores[a], &self.scores[b]);
+            match by {
+                SortBy::Money => b.money.cmp(&a.money),
+                SortBy::Depth => b.depth.cmp(&a.depth),
+                SortBy::Time => b.seconds.cmp(&a.seconds),
+                SortBy::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            }
+            .then(b.money.cmp(&a.money))
+        });
+        order
+    }
+
+    // One score per line: money, depth, seconds, seed, difficulty and then the name,
+    // which can have spaces in it. Lines that can't be read are skipped, so one bad
+    // line doesn't lose the rest.
+    pub fn parse(text: &str) -> Self {
+        let mut scores = HighScores::default();
+        for line in text.lines() {
+            if let Some(score) = Score::parse(line) {
+                scores.add(score);
+            }
+        }
+        scores
+    }
+
+    pub fn to_text(&self) -> String {
+        self.scores
+            .iter()
+            .map(|s| {
+                format!(
+                    "{} {} {} {} {} {}\n",
+                    s.money,
+                    s.depth,
+                    s.seconds,
+                    s.seed,
+                    s.difficulty.name(),
+                    s.name
+                )
+            })
+            .collect()
+    }
+
+    // A missing file just means nobody has finished a game yet.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(HighScores::parse(&text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(HighScores::default()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn odds(difficulty: Difficulty, find: Find) -> usize {
+        (0..DIG_ROLL)
+            .filter(|&roll| difficulty.find(roll) == find)
+            .count()
+    }
+
+    #[test]
+    fn difficulty_odds() {
+        let levels = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+        for find in [Find::Rock, Find::Flood, Find::CaveIn] {
+            assert!(odds(levels[0], find) < odds(levels[1], find));
+            assert!(odds(levels[1], find) < odds(levels[2], find));
+        }
+        for level in levels {
+            assert_eq!(TREASURE_ODDS as usize, odds(level, Find::Treasure));
+        }
+        // Most of the time it's just dirt.
+        assert!(odds(Difficulty::Hard, Find::Nothing) > DIG_ROLL as usize / 2);
+    }
+
+    fn score(name: &str, money: i32, depth: i32, seconds: i32) -> Score {
+        Score {
+            name: name.to_string(),
+            money,
+            depth,
+            seconds,
+            seed: 42,
+            difficulty: Difficulty::Normal,
+        }
+    }
+
+    fn some_scores() -> HighScores {
+        let mut s = HighScores::default();
+        s.add(score("bob", 500, 40, 600));
+        s.add(score("Alice", 2000, 10, 300));
+        s.add(score("carol", 1000, 55, 900));
+        s
+    }
+
+    fn names(scores: Vec<&Score>) -> Vec<&str> {
+        scores.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn difficulty_names() {
+        for d in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            assert_eq!(Some(d), Difficulty::from_name(d.name()));
+        }
+        assert_eq!(None, Difficulty::from_name("nightmare"));
+    }
+
+    #[test]
+    fn sorting() {
+        let s = some_scores();
+        assert_eq!(
+            vec!["Alice", "carol", "bob"],
+            names(s.sorted(SortBy::Money))
+        );
+        assert_eq!(
+            vec!["carol", "bob", "Alice"],
+            names(s.sorted(SortBy::Depth))
+        );
+        assert_eq!(vec!["carol", "bob", "Alice"], names(s.sorted(SortBy::Time)));
+        assert_eq!(vec!["Alice", "bob", "carol"], names(s.sorted(SortBy::Name)));
+    }
+
+    #[test]
+    fn ties_go_to_the_richer() {
+        let mut s = some_scores();
+        s.add(score("dave", 3000, 40, 10));
+        assert_eq!(
+            vec!["carol", "dave", "bob", "Alice"],
+            names(s.sorted(SortBy::Depth))
+        );
+    }
+
+    #[test]
+    fn only_the_best_are_kept() {
+        let mut s = HighScores::default();
+        for i in 0..MAX_SCORES as i32 + 5 {
+            s.add(score("x", i, 0, 0));
+        }
+        let sorted = s.sorted(SortBy::Money);
+        assert_eq!(MAX_SCORES, sorted.len());
+        assert_eq!(5, sorted.last().unwrap().money);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut s = some_scores();
+        s.add(Score {
+            name: "Grandpa Joe".to_string(),
+            seed: u64::MAX,
+            difficulty: Difficulty::Hard,
+            ..score("", 10, 1, 2)
+        });
+        let text = s.to_text();
+        assert!(text.contains("10 1 2 18446744073709551615 hard Grandpa Joe\n"));
+        let parsed = HighScores::parse(&text);
+        assert_eq!(s.sorted(SortBy::Money), parsed.sorted(SortBy::Money));
+    }
+
+    #[test]
+    fn names_keep_their_spaces() {
+        let mut s = HighScores::default();
+        s.add(score("  Grandpa   Joe ", 10, 1, 2));
+        let parsed = HighScores::parse(&s.to_text());
+        assert_eq!(
+            vec!["  Grandpa   Joe "],
+            names(parsed.sorted(SortBy::Money))
+        );
+    }
+
+    #[test]
+    fn bad_lines_are_skipped() {
+        for line in [
+            "1 2 3 4 easy",
+            "1 2 3 4 easy  ",
+            "lots 2 3 4 easy bob",
+            "1 2 3 -4 easy bob",
+            "1 2 3 4 tricky bob",
+            "",
+        ] {
+            let text = format!("5 6 7 8 hard alice\n{}\n9 9 9 9 easy carol\n", line);
+            let scores = HighScores::parse(&text);
+            assert_eq!(vec!["carol", "alice"], names(scores.sorted(SortBy::Money)));
+        }
+    }
+
+    #[test]
+    fn valid_names() {
+        assert!(Score::is_valid_name("Grandpa Joe"));
+        assert!(!Score::is_valid_name(""));
+        assert!(!Score::is_valid_name("   "));
+        assert!(!Score::is_valid_name("two\nlines"));
+    }
+
+    #[test]
+    fn records_are_kept() {
+        let mut s = HighScores::default();
+        // Deep, but poor.
+        s.add(score("digger", 0, 500, 0));
+        for i in 0..MAX_SCORES as i32 {
+            s.add(score("x", 1000 + i, 0, 0));
+        }
+        assert_eq!("digger", s.sorted(SortBy::Depth)[0].name);
+        assert_eq!(MAX_SCORES + 1, s.sorted(SortBy::Money).len());
+    }
+}
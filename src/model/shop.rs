@@ -0,0 +1,220 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::model::elevator::Elevator;
+use crate::model::inventory::Item;
+use crate::model::player::Player;
+
+pub struct ShopEntry {
+    pub item: Item,
+    pub price: i32,
+    pub quantity: i32,
+    pub description: String,
+}
+
+pub struct Shop {
+    entries: Vec<ShopEntry>,
+}
+
+impl Shop {
+    // Reads the price list, in the format of assets/shop.txt.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() < 4 {
+                return Err(format!("Line {}: missing fields", n + 1));
+            }
+            let item = Item::from_name(words[0]).ok_or(format!(
+                "Line {}: unknown item {}",
+                n + 1,
+                words[0]
+            ))?;
+            let price = words[1]
+                .parse()
+                .map_err(|e| format!("Line {}: bad price: {}", n + 1, e))?;
+            let quantity = words[2]
+                .parse()
+                .map_err(|e| format!("Line {}: bad quantity: {}", n + 1, e))?;
+            let description = words[3..].join(" ");
+            entries.push(ShopEntry {
+                item,
+                price,
+                quantity,
+                description,
+            });
+        }
+        Ok(Shop { entries })
+    }
+
+    pub fn entries(&self) -> &[ShopEntry] {
+        &self.entries
+    }
+
+    // Whether the player could buy the given entry right now.
+    pub fn can_buy(&self, player: &Player, elev: &Elevator, idx: usize) -> bool {
+        match self.entries.get(idx) {
+            // Picks are upgrades, not something to carry around.
+            Some(ShopEntry {
+                item: Item::Pick(pick),
+                price,
+                ..
+            }) => *pick > player.pick && player.money() >= *price,
+            // So are elevator parts, and they're fitted straight away.
+            Some(ShopEntry {
+                item: Item::Upgrade(upgrade),
+                price,
+                ..
+            }) => elev.can_upgrade(*upgrade) && player.money() >= *price,
+            Some(e) => {
+                player.money() >= e.price
+                    && player.inventory.can_add(e.item, e.quantity)
+                    // Nobody needs two of the same tool.
+                    && !(e.item.is_tool() && player.inventory.count(e.item) > 0)
+            }
+            None => false,
+        }
+    }
+
+    pub fn buy(&self, player: &mut Player, elev: &mut Elevator, idx: usize) -> bool {
+        let ok = self.can_buy(player, elev, idx);
+        if ok {
+            let e = &self.entries[idx];
+            match e.item {
+                Item::Pick(pick) => player.upgrade_pick(pick, e.price),
+                Item::Upgrade(upgrade) => player.pay_money(e.price) && elev.upgrade(upgrade),
+                _ => player.buy(e.item, e.quantity, e.price),
+            };
+        }
+        ok
+    }
+}
+
+impl Default for Shop {
+    fn default() -> Self {
+        Shop::parse(include_str!("../../assets/shop.txt")).expect("Invalid shop.txt")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::pick::Pick;
+
+    #[test]
+    fn parse_works() {
+        let shop = Shop::parse(
+            "# A comment.\n\
+             \n\
+             ladder  40  5  Some ladders\n\
+             pump\t500\t1\tA pump, for water\n",
+        )
+        .unwrap();
+        assert_eq!(2, shop.entries().len());
+        let e = &shop.entries()[0];
+        assert_eq!(Item::Ladder, e.item);
+        assert_eq!(40, e.price);
+        assert_eq!(5, e.quantity);
+        assert_eq!("Some ladders", e.description);
+        assert_eq!("A pump, for water", shop.entries()[1].description);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Shop::parse("ladder 40 5").is_err());
+        assert!(Shop::parse("ladder forty 5 Ladders").is_err());
+        assert!(Shop::parse("ladder 40 five Ladders").is_err());
+        assert!(Shop::parse("elephant 40 5 Elephants").is_err());
+    }
+
+    #[test]
+    fn data_file_is_valid() {
+        let shop = Shop::default();
+        assert!(!shop.entries().is_empty());
+    }
+
+    #[test]
+    fn buy_works() {
+        let shop = Shop::parse("ladder 40 5 Ladders\nlantern 300 1 A lantern").unwrap();
+        let mut p = Player::new();
+        let mut elev = Elevator::new(10);
+        let m1 = p.money();
+
+        assert!(shop.buy(&mut p, &mut elev, 0));
+        assert!(shop.buy(&mut p, &mut elev, 0));
+        assert_eq!(10, p.inventory.count(Item::Ladder));
+        assert_eq!(m1 - 80, p.money());
+
+        assert!(shop.buy(&mut p, &mut elev, 1));
+        assert_eq!(1, p.inventory.count(Item::Lantern));
+        // Only one lantern is needed.
+        assert!(!shop.can_buy(&p, &elev, 1));
+        assert!(!shop.buy(&mut p, &mut elev, 1));
+        assert_eq!(1, p.inventory.count(Item::Lantern));
+
+        assert!(!shop.buy(&mut p, &mut elev, 2));
+    }
+
+    #[test]
+    fn buy_pick() {
+        let shop = Shop::parse("iron_pick 100 1 Iron\nsteel_pick 200 1 Steel").unwrap();
+        let mut p = Player::new();
+        let mut elev = Elevator::new(10);
+        let m1 = p.money();
+
+        assert!(shop.buy(&mut p, &mut elev, 1));
+        assert_eq!(Pick::Steel, p.pick);
+        assert_eq!(m1 - 200, p.money());
+        assert_eq!(0, p.inventory.weight());
+        // Already have something better.
+        assert!(!shop.buy(&mut p, &mut elev, 0));
+        assert!(!shop.buy(&mut p, &mut elev, 1));
+        assert_eq!(Pick::Steel, p.pick);
+    }
+
+    #[test]
+    fn buy_elevator_parts() {
+        let shop = Shop::parse("elevator_motor 1000 1 A faster motor").unwrap();
+        let mut p = Player::new();
+        let mut elev = Elevator::new(10);
+        p.receive_money(5000);
+        let (m1, speed) = (p.money(), elev.top_speed());
+
+        assert!(shop.buy(&mut p, &mut elev, 0));
+        assert!(shop.buy(&mut p, &mut elev, 0));
+        assert!(elev.top_speed() > speed);
+        assert_eq!(m1 - 2000, p.money());
+        // That's the fastest there is.
+        assert!(!shop.buy(&mut p, &mut elev, 0));
+        assert_eq!(m1 - 2000, p.money());
+        assert_eq!(0, p.inventory.weight());
+    }
+
+    #[test]
+    fn buy_needs_money() {
+        let shop = Shop::parse("ladder 40 5 Ladders").unwrap();
+        let mut p = Player::new();
+        let mut elev = Elevator::new(10);
+        p.pay_money(p.money() - 39);
+        assert!(!shop.buy(&mut p, &mut elev, 0));
+        assert_eq!(0, p.inventory.count(Item::Ladder));
+        assert_eq!(39, p.money());
+    }
+}
@@ -0,0 +1,210 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::model::mineral::Mineral;
+use std::fs;
+use std::io::ErrorKind;
+
+// The counters other than the treasure, borrowed with & or &mut.
+macro_rules! named_counters {
+    ($stats:expr, $($borrow:tt)+) => {
+        [
+            ("tiles_dug", "Tiles dug", $($borrow)+ $stats.tiles_dug),
+            ("rocks_broken", "Rocks broken", $($borrow)+ $stats.rocks_broken),
+            ("energy_spent", "Energy spent", $($borrow)+ $stats.energy_spent),
+            ("deepest", "Deepest row reached", $($borrow)+ $stats.deepest),
+            ("rescues", "Rescues", $($borrow)+ $stats.rescues),
+            ("floods", "Floods survived", $($borrow)+ $stats.floods),
+            ("cave_ins", "Cave-ins survived", $($borrow)+ $stats.cave_ins),
+            ("ladders_built", "Ladders built", $($borrow)+ $stats.ladders_built),
+            ("dynamite_used", "Dynamite used", $($borrow)+ $stats.dynamite_used),
+            ("elevator_rows", "Rows ridden in the elevator", $($borrow)+ $stats.elevator_rows),
+        ]
+    };
+}
+
+fn treasure_key(mineral: Mineral) -> String {
+    format!("treasure_{}", mineral.name().to_lowercase())
+}
+
+// Lifetime totals, across every game played.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub tiles_dug: i32,
+    pub rocks_broken: i32,
+    treasure: [i32; Mineral::ALL.len()],
+    pub energy_spent: i32,
+    // Rows below the grass.
+    pub deepest: i32,
+    pub rescues: i32,
+    pub floods: i32,
+    pub cave_ins: i32,
+    pub ladders_built: i32,
+    pub dynamite_used: i32,
+    pub elevator_rows: i32,
+}
+
+impl Stats {
+    pub fn found(&mut self, mineral: Mineral) {
+        self.treasure[mineral as usize] += 1;
+    }
+
+    pub fn reached(&mut self, depth: i32) {
+        self.deepest = core::cmp::max(self.deepest, depth);
+    }
+
+    // Every number, with the name it's saved under and a description for the screen.
+    fn counters(&self) -> Vec<(String, String, i32)> {
+        let mut counters: Vec<_> = named_counters!(self, &)
+            .into_iter()
+            .map(|(key, label, n)| (key.to_string(), label.to_string(), *n))
+            .collect();
+        for (m, n) in Mineral::ALL.iter().zip(self.treasure.iter()) {
+            counters.push((treasure_key(*m), format!("{} found", m.name()), *n));
+        }
+        counters
+    }
+
+    // The same, by name, to be read into.
+    fn counters_mut(&mut self) -> Vec<(String, &mut i32)> {
+        let mut counters: Vec<_> = named_counters!(self, &mut)
+            .into_iter()
+            .map(|(key, _, n)| (key.to_string(), n))
+            .collect();
+        for (m, n) in Mineral::ALL.iter().zip(self.treasure.iter_mut()) {
+            counters.push((treasure_key(*m), n));
+        }
+        counters
+    }
+
+    // For the stats screen.
+    pub fn lines(&self) -> Vec<(String, i32)> {
+        self.counters()
+            .into_iter()
+            .map(|(_, label, n)| (label, n))
+            .collect()
+    }
+
+    // Reads "name number" lines, as written by to_text(). Names from some other
+    // version of the game are skipped.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut stats = Stats::default();
+        let mut counters = stats.counters_mut();
+        for (n, line) in text.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                [] => continue,
+                [key, value] => {
+                    let value = value
+                        .parse()
+                        .map_err(|e| format!("Line {}: bad number: {}", n + 1, e))?;
+                    if let Some((_, counter)) = counters.iter_mut().find(|(k, _)| k == key) {
+                        **counter = value;
+                    }
+                }
+                _ => return Err(format!("Line {}: expected a name and a number", n + 1)),
+            }
+        }
+        Ok(stats)
+    }
+
+    pub fn to_text(&self) -> String {
+        self.counters()
+            .into_iter()
+            .map(|(key, _, n)| format!("{} {}\n", key, n))
+            .collect()
+    }
+
+    // A missing file just means nothing has been played yet.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Stats::parse(&text),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Stats::default()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text()).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn some_stats() -> Stats {
+        let mut s = Stats {
+            tiles_dug: 120,
+            rocks_broken: 7,
+            ..Default::default()
+        };
+        s.found(Mineral::Gold);
+        s.found(Mineral::Gold);
+        s.found(Mineral::Coal);
+        s.reached(30);
+        s.reached(12);
+        s.elevator_rows = 400;
+        s
+    }
+
+    #[test]
+    fn counting() {
+        let s = some_stats();
+        assert_eq!(2, s.treasure[Mineral::Gold as usize]);
+        assert_eq!(1, s.treasure[Mineral::Coal as usize]);
+        assert_eq!(0, s.treasure[Mineral::Diamond as usize]);
+        assert_eq!(30, s.deepest);
+    }
+
+    #[test]
+    fn round_trip() {
+        let s = some_stats();
+        let text = s.to_text();
+        assert!(text.contains("tiles_dug 120\n"));
+        assert!(text.contains("treasure_gold 2\n"));
+        assert_eq!(Ok(s), Stats::parse(&text));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Stats::parse("tiles_dug").is_err());
+        assert!(Stats::parse("tiles_dug lots").is_err());
+        assert!(Stats::parse("tiles_dug 1 2").is_err());
+        // Unknown names and blank lines are fine.
+        let s = Stats::parse("\nmonsters_slain 3\nfloods 2\n").unwrap();
+        assert_eq!(2, s.floods);
+    }
+
+    #[test]
+    fn lines() {
+        let lines = some_stats().lines();
+        assert!(lines.contains(&("Tiles dug".to_string(), 120)));
+        assert!(lines.contains(&("Gold found".to_string(), 2)));
+        assert_eq!(10 + Mineral::ALL.len(), lines.len());
+    }
+
+    #[test]
+    fn load_and_save() {
+        let path = std::env::temp_dir().join(format!("miner_stats_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(Ok(Stats::default()), Stats::load(path));
+        let s = some_stats();
+        s.save(path).unwrap();
+        assert_eq!(Ok(s), Stats::load(path));
+        fs::remove_file(path).unwrap();
+    }
+}
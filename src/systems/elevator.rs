@@ -15,15 +15,81 @@
 #![warn(clippy::all)]
 
 use crate::constants::*;
-use crate::model::elevator::Elevator;
-use crate::model::player::Player;
+use crate::model::animation::Animation;
+use crate::model::elevator::{Elevator, RIDER_WEIGHT};
+use crate::model::player::{Player, PlayerNumber};
+use crate::model::position::Position;
+use crate::model::run::Run;
+use crate::model::stats::Stats;
 use bevy::prelude::*;
 
-pub fn move_elevator(mut elev: ResMut<Elevator>, mut player: ResMut<Player>) {
-    let depth = elev.depth();
-    let player_in_elevator = player.x == ELEVATOR_SHAFT_X && (player.y - SKY_HEIGHT) == depth;
-    elev.move_towards_target();
-    if player_in_elevator {
-        player.y += elev.depth() as i32 - depth as i32;
+pub fn setup(mut commands: Commands) {
+    spawn_elevator(&mut commands, ELEVATOR_SHAFT_X, MAX_ELEVATOR_DEPTH, 0);
+}
+
+// An elevator for the shaft in column x, with the car at the given depth.
+pub fn spawn_elevator(commands: &mut Commands, x: i32, max_depth: i32, depth: i32) {
+    let mut elev = Elevator::new(max_depth);
+    elev.park(depth);
+    let pos = Position::new(x, SKY_HEIGHT + elev.depth());
+    commands.spawn_bundle((elev, pos, Animation::new(pos)));
+}
+
+// Everyone in the elevator rides along, and weighs it down. The riders split the
+// bill for the power, or player one pays if nobody is aboard, and everyone aboard
+// gets hurt if the cable snaps.
+pub fn move_elevator(
+    mut elevators: Query<(&mut Elevator, &mut Position), Without<Player>>,
+    mut players: Query<(&PlayerNumber, &mut Player, &mut Position)>,
+    mut stats: ResMut<Stats>,
+    mut run: ResMut<Run>,
+) {
+    for (mut elev, mut car) in elevators.iter_mut() {
+        let load = players
+            .iter()
+            .filter(|(_, _, pos)| **pos == *car)
+            .map(|(_, player, _)| RIDER_WEIGHT + player.inventory.weight())
+            .sum();
+        elev.set_load(load);
+        let depth = elev.depth();
+        elev.update(ELEVATOR_TICK as f32);
+        elev.check_wear(&mut run.breakdown_rng);
+        if elev.depth() != depth {
+            for (_, _, mut pos) in players.iter_mut().filter(|(_, _, pos)| **pos == *car) {
+                pos.y += elev.depth() - depth;
+                stats.elevator_rows += 1;
+            }
+            car.y = SKY_HEIGHT + elev.depth();
+        }
+
+        if let Some(rows) = elev.take_crash() {
+            for (_, mut player, _) in players.iter_mut().filter(|(_, _, pos)| **pos == *car) {
+                for _ in 0..rows {
+                    player.fall();
+                }
+                player.land(false);
+            }
+        }
+        let bill = elev.take_bill();
+        if bill > 0 {
+            let mut riders: Vec<PlayerNumber> = players
+                .iter()
+                .filter(|(_, _, pos)| **pos == *car)
+                .map(|(number, ..)| *number)
+                .collect();
+            riders.sort_by_key(|number| number.0);
+            if riders.is_empty() {
+                riders.push(PlayerNumber(0));
+            }
+            let shares = Elevator::split_bill(bill, riders.len());
+            for (number, share) in riders.into_iter().zip(shares) {
+                if let Some((_, mut player, _)) = players.iter_mut().find(|(&n, ..)| n == number) {
+                    if !player.pay_on_credit(share) {
+                        // Bankrupt.
+                        player.end_run();
+                    }
+                }
+            }
+        }
     }
 }
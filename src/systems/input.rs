@@ -15,99 +15,540 @@
 #![warn(clippy::all)]
 
 use crate::constants::*;
+use crate::model::bank::LOAN_STEP;
+use crate::model::dynamite::{Charges, Dynamite};
 use crate::model::elevator::Elevator;
+use crate::model::energy::{BUDGET_STEP, ENERGY_STEP};
+use crate::model::inventory::Item;
+use crate::model::key_repeat::{Direction, KeyRepeat, MoveBuffer, RepeatSettings};
 use crate::model::map::{Map, TileType};
-use crate::model::player::Player;
+use crate::model::movement;
+use crate::model::path::{find_path, find_path_where, AutoWalk};
+use crate::model::player::{open_slots, Player, PlayerNumber, MAX_ENERGY};
+use crate::model::position::{self, Position, Target};
+use crate::model::pump::{Pump, Pumps};
+use crate::model::score::SortBy;
+use crate::model::shop::Shop;
+use crate::model::stats::Stats;
+use crate::systems::elevator::spawn_elevator;
+use crate::systems::player::{build_ladder, rescue, spawn_player, take_ladder};
 use bevy::{
     prelude::*,
     render::camera::{ActiveCameras, Camera},
 };
 
+// The map tile under the mouse cursor, if any.
+#[derive(Default)]
+pub struct HoveredTile(pub Option<(i32, i32)>);
+
+// What is shown over the mine.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Screen {
+    #[default]
+    Game,
+    Stats,
+    Scores(SortBy),
+}
+
+pub fn screen_input(mut screen: ResMut<Screen>, keyboard_input: Res<Input<KeyCode>>) {
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        *screen = match *screen {
+            Screen::Game => Screen::Stats,
+            Screen::Stats => Screen::Scores(SortBy::Money),
+            Screen::Scores(_) => Screen::Game,
+        };
+    } else if keyboard_input.just_pressed(KeyCode::S) {
+        if let Screen::Scores(by) = *screen {
+            *screen = Screen::Scores(by.next());
+        }
+    }
+}
+
+// Something a player can do with a key or a gamepad button.
+#[derive(Clone, Copy)]
+pub enum Action {
+    Left,
+    Right,
+    Up,
+    Down,
+    Ladder,
+    TakeLadder,
+    Pump,
+    Dynamite,
+    Go,
+    Rescue,
+    Call,
+    Pick,
+    Shaft,
+    Repair,
+}
+
+impl Action {
+    const ALL: [Action; 14] = [
+        Action::Left,
+        Action::Right,
+        Action::Up,
+        Action::Down,
+        Action::Ladder,
+        Action::TakeLadder,
+        Action::Pump,
+        Action::Dynamite,
+        Action::Go,
+        Action::Rescue,
+        Action::Call,
+        Action::Pick,
+        Action::Shaft,
+        Action::Repair,
+    ];
+}
+
+const DIRECTIONS: [(Action, Direction); 4] = [
+    (Action::Left, Direction::Left),
+    (Action::Right, Direction::Right),
+    (Action::Up, Direction::Up),
+    (Action::Down, Direction::Down),
+];
+
+// Player one has the arrow keys and letters, and player two the numpad or the
+// first gamepad. Auto-walk follows the mouse, so it's player one's alone.
+fn keys(player: usize, action: Action) -> &'static [KeyCode] {
+    match (player, action) {
+        (0, Action::Left) => &[KeyCode::Left],
+        (0, Action::Right) => &[KeyCode::Right],
+        (0, Action::Up) => &[KeyCode::Up],
+        (0, Action::Down) => &[KeyCode::Down],
+        (0, Action::Ladder) => &[KeyCode::L],
+        (0, Action::TakeLadder) => &[KeyCode::U],
+        (0, Action::Pump) => &[KeyCode::P],
+        (0, Action::Dynamite) => &[KeyCode::D],
+        (0, Action::Go) => &[KeyCode::G],
+        (0, Action::Rescue) => &[KeyCode::R],
+        (0, Action::Call) => &[KeyCode::Space],
+        (0, Action::Pick) => &[KeyCode::LShift, KeyCode::RShift],
+        (0, Action::Shaft) => &[KeyCode::K],
+        (0, Action::Repair) => &[KeyCode::W],
+        (_, Action::Left) => &[KeyCode::Numpad4],
+        (_, Action::Right) => &[KeyCode::Numpad6],
+        (_, Action::Up) => &[KeyCode::Numpad8],
+        (_, Action::Down) => &[KeyCode::Numpad2, KeyCode::Numpad5],
+        (_, Action::Ladder) => &[KeyCode::Numpad1],
+        (_, Action::TakeLadder) => &[KeyCode::Numpad7],
+        (_, Action::Pump) => &[KeyCode::Numpad3],
+        (_, Action::Dynamite) => &[KeyCode::Numpad0],
+        (_, Action::Go) => &[],
+        (_, Action::Rescue) => &[KeyCode::Numpad9],
+        (_, Action::Call) => &[KeyCode::NumpadEnter],
+        (_, Action::Pick) => &[KeyCode::NumpadDecimal],
+        (_, Action::Shaft) => &[KeyCode::NumpadDivide],
+        (_, Action::Repair) => &[KeyCode::NumpadMultiply],
+    }
+}
+
+fn buttons(player: usize, action: Action) -> &'static [GamepadButtonType] {
+    if player == 0 {
+        return &[];
+    }
+    match action {
+        Action::Left => &[GamepadButtonType::DPadLeft],
+        Action::Right => &[GamepadButtonType::DPadRight],
+        Action::Up => &[GamepadButtonType::DPadUp],
+        Action::Down => &[GamepadButtonType::DPadDown],
+        Action::Ladder => &[GamepadButtonType::South],
+        Action::TakeLadder => &[GamepadButtonType::LeftTrigger],
+        Action::Pump => &[GamepadButtonType::West],
+        Action::Dynamite => &[GamepadButtonType::East],
+        Action::Go => &[],
+        Action::Rescue => &[GamepadButtonType::Select],
+        Action::Call => &[GamepadButtonType::North],
+        Action::Pick => &[GamepadButtonType::RightTrigger],
+        Action::Shaft => &[GamepadButtonType::LeftThumb],
+        Action::Repair => &[GamepadButtonType::RightThumb],
+    }
+}
+
+// One player's share of the keyboard and gamepad.
+pub struct Controls<'a> {
+    player: usize,
+    keyboard_input: &'a Input<KeyCode>,
+    gamepad_input: &'a Input<GamepadButton>,
+}
+
+impl<'a> Controls<'a> {
+    pub fn new(
+        player: usize,
+        keyboard_input: &'a Input<KeyCode>,
+        gamepad_input: &'a Input<GamepadButton>,
+    ) -> Self {
+        Controls {
+            player,
+            keyboard_input,
+            gamepad_input,
+        }
+    }
+
+    fn buttons(&self, action: Action) -> impl Iterator<Item = GamepadButton> {
+        let pad = Gamepad(0);
+        buttons(self.player, action)
+            .iter()
+            .map(move |&b| GamepadButton(pad, b))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.keyboard_input
+            .any_just_pressed(keys(self.player, action).iter().copied())
+            || self.gamepad_input.any_just_pressed(self.buttons(action))
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keyboard_input
+            .any_pressed(keys(self.player, action).iter().copied())
+            || self.gamepad_input.any_pressed(self.buttons(action))
+    }
+
+    fn any_just_pressed(&self) -> bool {
+        Action::ALL.iter().any(|&a| self.just_pressed(a))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
-    mut player: ResMut<Player>,
+    mut commands: Commands,
+    mut players: Query<(
+        &PlayerNumber,
+        &mut Player,
+        &mut Position,
+        &mut Target,
+        &mut KeyRepeat,
+        &mut MoveBuffer,
+    )>,
+    mut elevators: Query<(&mut Elevator, &Position), Without<Player>>,
     mut map: ResMut<Map>,
-    mut elev: ResMut<Elevator>,
+    mut pumps: ResMut<Pumps>,
+    mut charges: ResMut<Charges>,
+    mut walk: ResMut<AutoWalk>,
+    mut stats: ResMut<Stats>,
     keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    settings: Res<RepeatSettings>,
+    time: Res<Time>,
 ) {
-    let depth = elev.depth();
-    let player_in_elevator = player.x == ELEVATOR_SHAFT_X && (player.y - SKY_HEIGHT) == depth;
-
-    if keyboard_input.just_pressed(KeyCode::Left) {
-        if player.x > 1 {
-            player.target_x = player.x - 1;
+    let joined: Vec<PlayerNumber> = players.iter().map(|(number, ..)| *number).collect();
+    for PlayerNumber(idx) in open_slots(&joined) {
+        // Pressing any of the second player's controls brings them into the game.
+        if Controls::new(idx, &keyboard_input, &gamepad_input).any_just_pressed() {
+            spawn_player(&mut commands, idx);
         }
-    } else if keyboard_input.just_pressed(KeyCode::Right) {
-        if player.x < MAP_WIDTH - 3 {
-            player.target_x = player.x + 1;
-        }
-    } else if keyboard_input.just_pressed(KeyCode::Up) {
-        if player_in_elevator {
-            elev.set_target_depth(depth - 1);
-        } else if player.y > SKY_HEIGHT {
-            player.target_y = player.y - 1;
-        }
-    } else if keyboard_input.just_pressed(KeyCode::Down) {
-        if player_in_elevator {
-            elev.set_target_depth(depth + 1);
-        } else if player.y < MAP_HEIGHT - 2 {
-            player.target_y = player.y + 1;
+    }
+
+    for (&PlayerNumber(idx), mut player, mut pos, mut target, mut repeat, mut buffer) in
+        players.iter_mut()
+    {
+        let controls = Controls::new(idx, &keyboard_input, &gamepad_input);
+        let mut elev = elevators.iter_mut().find(|(_, car)| **car == *pos);
+
+        let pressed = DIRECTIONS
+            .iter()
+            .find(|&&(a, _)| controls.just_pressed(a))
+            .map(|&(_, dir)| dir);
+        let held = DIRECTIONS
+            .iter()
+            .find(|&&(a, _)| controls.pressed(a))
+            .map(|&(_, dir)| dir);
+        if idx == 0 && pressed.is_some() {
+            // Taking over from auto-walk.
+            walk.stop();
         }
-    } else if keyboard_input.just_pressed(KeyCode::L) {
-        if player.x < ELEVATOR_SHAFT_X && map.tile(player.x, player.y) == TileType::Empty {
-            if player.use_energy(5) {
-                map.set_tile(player.x, player.y, TileType::Ladder);
+
+        // Moves wait in the buffer for the next tick. In the elevator, up and down
+        // work the elevator instead.
+        if let Some((dir, repeat)) = repeat.update(pressed, held, &settings, time.delta_seconds()) {
+            match (dir, elev.as_mut()) {
+                (Direction::Up, Some((elev, _))) => elev.ride(-1),
+                (Direction::Down, Some((elev, _))) => elev.ride(1),
+                _ => buffer.push(dir, repeat),
             }
         }
-    } else if keyboard_input.just_pressed(KeyCode::R) {
-        // Rescue!
-        if player.x < ELEVATOR_SHAFT_X && player.y > GRASS_LEVEL {
-            player.pay_money(250);
-            player.x = ELEVATOR_SHAFT_X;
-            player.y = SKY_HEIGHT + depth;
-            player.target_x = player.x;
-            player.target_y = player.y;
-            elev.set_target_depth(0);
+
+        if controls.just_pressed(Action::Ladder) {
+            if build_ladder(&mut player, &mut map, pos.x, pos.y) {
+                stats.ladders_built += 1;
+            }
+        } else if controls.just_pressed(Action::TakeLadder) {
+            take_ladder(&mut player, &mut map, pos.x, pos.y);
+        } else if controls.just_pressed(Action::Pump) {
+            if pos.x < ELEVATOR_SHAFT_X
+                && !map.is_shaft(pos.x)
+                && pos.y > GRASS_LEVEL
+                && map.tile(pos.x, pos.y) == TileType::Empty
+                && player.inventory.remove(Item::Pump, 1)
+            {
+                map.set_tile(pos.x, pos.y, TileType::Pump);
+                pumps.add(Pump::new(pos.x, pos.y));
+            }
+        } else if controls.just_pressed(Action::Dynamite) {
+            // Light a stick of dynamite, and get clear.
+            if pos.x < ELEVATOR_SHAFT_X
+                && !map.is_shaft(pos.x)
+                && pos.y > SKY_HEIGHT
+                && player.inventory.count(Item::Dynamite) > 0
+                && charges.add(Dynamite::new(pos.x, pos.y))
+            {
+                player.inventory.remove(Item::Dynamite, 1);
+                stats.dynamite_used += 1;
+            }
+        } else if controls.just_pressed(Action::Go) {
+            // Walk to the tunnel next to the nearest elevator shaft.
+            if let Some(path) = find_path_where(
+                &map,
+                (pos.x, pos.y),
+                |px, py| map.is_shaft(px + 1) && !movement::will_fall(&map, px, py),
+                |px, _| map.shafts().map(|x| (x - 1 - px).abs()).min().unwrap_or(0),
+            ) {
+                walk.start(path);
+            }
+        } else if controls.just_pressed(Action::Rescue) {
+            // Rescue, by the nearest working elevator!
+            if !map.is_shaft(pos.x) && pos.y > GRASS_LEVEL {
+                if let Some((mut elev, car)) = elevators
+                    .iter_mut()
+                    .filter(|(elev, _)| elev.breakdown().is_none())
+                    .min_by_key(|(_, car)| (car.x - pos.x).abs())
+                {
+                    rescue(&mut player, &mut pos, &mut target, &mut elev, car);
+                    stats.rescues += 1;
+                }
+            }
+        } else if controls.just_pressed(Action::Shaft) {
+            // Put an elevator down the tunnel the player is in, with the car waiting
+            // for them.
+            let run = map.open_run(pos.x);
+            if pos.y > SKY_HEIGHT && pos.y < GRASS_LEVEL + run && player.money() >= SHAFT_COST {
+                if let Some((max_depth, ladders)) = map.build_shaft(pos.x) {
+                    player.pay_money(SHAFT_COST);
+                    for _ in 0..ladders {
+                        player.take_ladder();
+                    }
+                    spawn_elevator(&mut commands, pos.x, max_depth, pos.y - SKY_HEIGHT);
+                    walk.stop();
+                }
+            }
+        } else if controls.just_pressed(Action::Repair) {
+            // Fix up the elevator from the top of its shaft.
+            if pos.y == SKY_HEIGHT {
+                if let Some((mut elev, _)) =
+                    elevators.iter_mut().find(|(_, car)| car.x == pos.x + 1)
+                {
+                    let cost = elev.repair_cost();
+                    if cost > 0 && player.pay_money(cost) {
+                        elev.repair();
+                    }
+                }
+            }
         }
+
+        player.use_pick = controls.pressed(Action::Pick);
     }
+}
 
-    player.rock_hammer =
-        keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
+// Whoever is standing at the bank gets served.
+pub fn shop_input(
+    mut players: Query<(&mut Player, &Position)>,
+    mut elevators: Query<(&mut Elevator, &Position), Without<Player>>,
+    shop: Res<Shop>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    let (mut player, pos) = match players
+        .iter_mut()
+        .find(|(_, pos)| pos.x == BANK_X && pos.y == SKY_HEIGHT)
+    {
+        Some(p) => p,
+        None => return,
+    };
+    let keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+        KeyCode::Key0,
+    ];
+    for (idx, key) in keys.iter().enumerate() {
+        // Elevator parts go to the nearest shaft that can take them.
+        if keyboard_input.just_pressed(*key) {
+            if let Some((mut elev, _)) = elevators
+                .iter_mut()
+                .filter(|(elev, _)| shop.can_buy(&player, elev, idx))
+                .min_by_key(|(_, car)| (car.x - pos.x).abs())
+            {
+                shop.buy(&mut player, &mut elev, idx);
+            }
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Q) {
+        // Retire, and see how it went.
+        player.end_run();
+    } else if keyboard_input.just_pressed(KeyCode::F) {
+        player.fill_energy();
+    } else if keyboard_input.just_pressed(KeyCode::E) {
+        let n = i32::min(ENERGY_STEP, MAX_ENERGY - player.energy());
+        player.buy_energy(n);
+    } else if keyboard_input.just_pressed(KeyCode::M) {
+        player.buy_energy_for(BUDGET_STEP);
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Equals, KeyCode::NumpadAdd]) {
+        player.borrow(LOAN_STEP);
+    } else if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        player.repay(LOAN_STEP);
+    }
 }
 
+// Calling the elevator takes a button beside the shaft on your level. Where there
+// isn't one, the call key puts one up.
 pub fn elevator_input(
-    mut elev: ResMut<Elevator>,
-    player: Res<Player>,
+    mut elevators: Query<(&mut Elevator, &Position), Without<Player>>,
+    mut players: Query<(&PlayerNumber, &mut Player, &Position)>,
     keyboard_input: Res<Input<KeyCode>>,
+    gamepad_input: Res<Input<GamepadButton>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        elev.set_target_depth(player.y - SKY_HEIGHT);
-    } else if keyboard_input.just_pressed(KeyCode::H) {
-        elev.set_target_depth(-SKY_HEIGHT);
-    } else if keyboard_input.just_pressed(KeyCode::B) {
-        elev.set_target_depth(MAP_HEIGHT - SKY_HEIGHT);
+    for (mut elev, car) in elevators.iter_mut() {
+        for (&PlayerNumber(idx), mut player, pos) in players.iter_mut() {
+            if pos.x != car.x - 1
+                || !Controls::new(idx, &keyboard_input, &gamepad_input).just_pressed(Action::Call)
+            {
+                continue;
+            }
+            let depth = pos.y - SKY_HEIGHT;
+            if elev.has_button(depth) {
+                elev.request(depth);
+            } else if player.money() >= CALL_BUTTON_COST && elev.add_button(depth) {
+                player.pay_money(CALL_BUTTON_COST);
+            }
+        }
+        // H and B are for player one, and work the elevator they're in or beside.
+        let beside = players
+            .iter()
+            .any(|(&number, _, pos)| number == PlayerNumber(0) && (pos.x - car.x).abs() <= 1);
+        if !beside {
+            continue;
+        }
+        if keyboard_input.just_pressed(KeyCode::H) {
+            elev.request(0);
+        } else if keyboard_input.just_pressed(KeyCode::B) {
+            elev.request(MAP_HEIGHT - SKY_HEIGHT);
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn mouse_input(
+    mut players: Query<(&PlayerNumber, &mut Player, &Position, &mut Target)>,
+    mut map: ResMut<Map>,
+    mut walk: ResMut<AutoWalk>,
+    mut hovered: ResMut<HoveredTile>,
+    mut stats: ResMut<Stats>,
+    windows: Res<Windows>,
+    active_cameras: Res<ActiveCameras>,
+    camera_transform_query: Query<&Transform, With<Camera>>,
+    mouse_input: Res<Input<MouseButton>>,
+) {
+    let tile = windows.get_primary().and_then(|window| {
+        let cursor = window.cursor_position()?;
+        let camera = active_cameras.get("camera_2d")?.entity?;
+        let tf = camera_transform_query.get(camera).ok()?;
+        // The cursor is relative to the bottom left corner of the window, and the
+        // camera is looking at the middle of it.
+        let size = Vec2::new(window.width(), window.height());
+        let world = tf.compute_matrix() * (cursor - size / 2.0).extend(0.0).extend(1.0);
+        // Tiles are 64 pixels, centered on their coordinates, with y going down.
+        let (x, y) = (
+            (world.x / 64.0).round() as i32,
+            -(world.y / 64.0).round() as i32,
+        );
+        map.in_bounds(x, y).then_some((x, y))
+    });
+    if hovered.0 != tile {
+        hovered.0 = tile;
+    }
+
+    let (x, y) = match tile {
+        Some(t) => t,
+        None => return,
+    };
+    // The mouse belongs to player one.
+    let (_, mut player, pos, mut target) = match players
+        .iter_mut()
+        .find(|(&number, ..)| number == PlayerNumber(0))
+    {
+        Some(p) => p,
+        None => return,
+    };
+    let next_to_player = (x - pos.x).abs() + (y - pos.y).abs() == 1;
+    if mouse_input.just_pressed(MouseButton::Left) {
+        walk.stop();
+        if next_to_player
+            && matches!(
+                map.tile(x, y),
+                TileType::Dirt | TileType::Rock { .. } | TileType::Treasure { .. }
+            )
+        {
+            // Dig.
+            *target = Position::new(x, y).target();
+        } else if let Some(path) = find_path(&map, (pos.x, pos.y), (x, y)) {
+            walk.start(path);
+        }
+    } else if mouse_input.just_pressed(MouseButton::Right)
+        && (next_to_player || (x, y) == (pos.x, pos.y))
+        && build_ladder(&mut player, &mut map, x, y)
+    {
+        stats.ladders_built += 1;
+    }
+}
+
+// Follows the players, zooming out if need be to keep them all on screen.
 pub fn camera_input(
     active_cameras: Res<ActiveCameras>,
     mut camera_transform_query: Query<(&mut Transform,), With<Camera>>,
-    player: Res<Player>,
+    players: Query<&Position, With<Player>>,
+    windows: Res<Windows>,
     keyboard_input: Res<Input<KeyCode>>,
     time: Res<Time>,
+    mut zoom: Local<f32>,
 ) {
     const ZOOM_SPEED: f32 = 10.0;
+    // How quickly the camera catches up with the players.
+    const CAMERA_SPEED: f32 = 8.0;
+    // Tiles to leave between the players and the edge of the screen.
+    const MARGIN: i32 = 2;
+
+    if keyboard_input.pressed(KeyCode::X) {
+        *zoom -= ZOOM_SPEED * time.delta_seconds();
+    } else if keyboard_input.pressed(KeyCode::Z) {
+        *zoom += ZOOM_SPEED * time.delta_seconds();
+    }
+    *zoom = zoom.max(1.0);
+
+    let (left, top, right, bottom) = position::bounds(players.iter().copied());
+    let fit = windows.get_primary().map_or(1.0, |window| {
+        let width = (64 * (right - left + 1 + 2 * MARGIN)) as f32;
+        let height = (64 * (bottom - top + 1 + 2 * MARGIN)) as f32;
+        f32::max(width / window.width(), height / window.height())
+    });
 
     if let Some(active_camera_entity) = active_cameras.get("camera_2d").and_then(|ac| ac.entity) {
         if let Ok((mut tf,)) = camera_transform_query.get_mut(active_camera_entity) {
-            if keyboard_input.pressed(KeyCode::X) {
-                tf.scale -= Vec3::splat(ZOOM_SPEED) * time.delta_seconds();
-            } else if keyboard_input.pressed(KeyCode::Z) {
-                tf.scale += Vec3::splat(ZOOM_SPEED) * time.delta_seconds();
-            }
-
-            tf.scale = tf.scale.max(Vec3::ONE);
-            tf.translation.x = (64 * player.x) as f32;
-            tf.translation.y = (-64 * player.y) as f32;
+            tf.scale = Vec3::splat(zoom.max(fit));
+            // Glide after the players, rather than jumping a tile at a time.
+            let goal = Vec3::new(
+                (32 * (left + right)) as f32,
+                (-32 * (top + bottom)) as f32,
+                tf.translation.z,
+            );
+            let t = f32::min(1.0, CAMERA_SPEED * time.delta_seconds());
+            tf.translation = tf.translation.lerp(goal, t);
         }
     }
 }
@@ -18,4 +18,6 @@ pub mod elevator;
 pub mod input;
 pub mod player;
 pub mod render;
+pub mod scores;
+pub mod stats;
 pub mod text;
@@ -15,129 +15,441 @@
 #![warn(clippy::all)]
 
 use crate::constants::*;
+use crate::model::animation::{Animation, Pose};
+use crate::model::dynamite::Charges;
 use crate::model::elevator::Elevator;
+use crate::model::hazard::{self, cave_in, flood, Air};
+use crate::model::inventory::Item;
+use crate::model::key_repeat::{KeyRepeat, MoveBuffer};
 use crate::model::map::{Map, TileType};
-use crate::model::player::Player;
+use crate::model::mineral::Mineral;
+use crate::model::movement;
+use crate::model::path::AutoWalk;
+use crate::model::player::{start_position, Player, PlayerNumber};
+use crate::model::position::{Position, Target};
+use crate::model::pump::Pumps;
+use crate::model::run::Run;
+use crate::model::score::{Find, DIG_ROLL};
+use crate::model::stats::Stats;
 use bevy::prelude::*;
 use rand::prelude::*;
 
-pub fn move_player(mut player: ResMut<Player>, mut map: ResMut<Map>, elev: Res<Elevator>) {
-    let depth = elev.depth();
-    let player_in_elevator = player.x == ELEVATOR_SHAFT_X && (player.y - SKY_HEIGHT) == depth;
+pub fn setup(mut commands: Commands) {
+    spawn_player(&mut commands, 0);
+}
+
+// A new miner, at the start.
+pub fn spawn_player(commands: &mut Commands, number: usize) {
+    let pos = start_position();
+    commands.spawn_bundle((
+        Player::new(),
+        PlayerNumber(number),
+        pos,
+        pos.target(),
+        Animation::new(pos),
+        KeyRepeat::default(),
+        MoveBuffer::default(),
+    ));
+}
+
+pub fn move_player(
+    mut players: Query<
+        (
+            &mut Player,
+            &mut Position,
+            &mut Target,
+            &mut Animation,
+            &mut MoveBuffer,
+        ),
+        Without<Elevator>,
+    >,
+    elevators: Query<&Position, With<Elevator>>,
+    mut map: ResMut<Map>,
+    mut stats: ResMut<Stats>,
+    mut run: ResMut<Run>,
+) {
+    // Flooding and cave-ins spare the tiles the players are standing on.
+    let spared: Vec<_> = players.iter().map(|(_, pos, ..)| (pos.x, pos.y)).collect();
+    let mut cave_ins = vec![];
+    for (mut player, mut pos, mut target, mut anim, mut buffer) in players.iter_mut() {
+        let in_elevator = elevators.iter().any(|car| *car == *pos);
+        let falling = !in_elevator && movement::will_fall(&map, pos.x, pos.y);
+        player.step_wait = f32::max(0.0, player.step_wait - PLAYER_TICK as f32);
+        if !falling && player.step_wait == 0.0 {
+            // The next move, if one was pressed in time. Riders can only step out sideways.
+            if let Some(dir) = buffer.pop() {
+                *target = dir.target(*pos);
+            }
+        }
+
+        let (from, aim) = (*pos, *target);
+        let tile = map.tile(aim.x, aim.y);
+        cave_ins.extend(step(
+            &mut player,
+            &mut pos,
+            &mut target,
+            &mut map,
+            &mut stats,
+            &mut run,
+            in_elevator,
+            &spared,
+        ));
+
+        let dug = tile != map.tile(aim.x, aim.y);
+        if let Some(pose) = Pose::of_step(from, *pos, falling, dug) {
+            anim.start(pose, aim.x - from.x);
+        }
+    }
+    for (x, y) in cave_ins {
+        for (mut player, pos, ..) in players.iter_mut() {
+            hurt_by_cave_in(&mut player, &pos, x, y);
+        }
+    }
+}
+
+// Moves one player, digging as they go. Returns where a cave-in started, if the
+// digging set one off.
+#[allow(clippy::too_many_arguments)]
+fn step(
+    player: &mut Player,
+    pos: &mut Position,
+    target: &mut Target,
+    map: &mut Map,
+    stats: &mut Stats,
+    run: &mut Run,
+    player_in_elevator: bool,
+    spared: &[(i32, i32)],
+) -> Option<(i32, i32)> {
+    let mut caved_in = None;
+    let energy = player.energy();
+    let falling = !player_in_elevator && movement::will_fall(map, pos.x, pos.y);
+    if !falling {
+        let in_water = map.tile(pos.x, pos.y) == TileType::Water;
+        player.land(in_water);
+    }
 
     if player_in_elevator {
         // Let the elevator move us instead.
-        player.target_y = player.y;
-    } else if map.tile(player.x, player.y + 1) == TileType::Empty
-        && map.tile(player.x, player.y) != TileType::Ladder
-    {
+        target.y = pos.y;
+    } else if falling {
         // Fall down.
-        player.target_x = player.x;
-        player.target_y = player.y + 1;
+        target.x = pos.x;
+        target.y = pos.y + 1;
+    } else if player.step_wait > 0.0 {
+        // Still catching our breath from the last step.
+        return None;
+    }
+
+    // Climbing takes a ladder, and so does digging overhead.
+    if !player_in_elevator && !movement::can_step(map, pos.x, pos.y, target.x, target.y) {
+        *target = pos.target();
     }
 
     // Change the target tile, if needed.
-    match map.tile(player.target_x, player.target_y) {
+    match map.tile(target.x, target.y) {
         TileType::Dirt => {
-            if player.use_energy(1) {
-                match thread_rng().gen_range(0..50) {
-                    0..=9 => {
-                        // Rock.
-                        map.set_tile(
-                            player.target_x,
-                            player.target_y,
-                            TileType::Rock {
-                                hardness: ((thread_rng().gen_range(0..50) + player.target_y) / 25)
-                                    as u8,
-                            },
-                        );
-                    }
-                    10..=12 => {
-                        // Treasure.
-                        map.set_tile(
-                            player.target_x,
-                            player.target_y,
-                            TileType::Treasure {
-                                value: ((thread_rng().gen_range(0..50) + player.target_y) / 35)
-                                    as u8,
-                            },
-                        );
-                    }
-                    20 => {
-                        // Flooding.
-                        for x in (player.target_x - 5)..=(player.target_x + 5) {
-                            for y in (player.target_y - 5)..=(player.target_y + 5) {
-                                if thread_rng().gen_range(0..100) < 10
-                                    && (map.tile(x, y) == TileType::Empty
-                                        || map.tile(x, y) == TileType::Dirt)
-                                    && !(x == player.x && y == player.y)
-                                    && x < ELEVATOR_SHAFT_X
-                                {
-                                    map.set_tile(x, y, TileType::Water);
-                                }
-                            }
-                        }
-                    }
-                    21 => {
-                        // Cave-in.
-                        for x in (player.target_x - 5)..=(player.target_x + 5) {
-                            for y in (player.target_y - 5)..=(player.target_y + 5) {
-                                if thread_rng().gen_range(0..100) < 50
-                                    && (map.tile(x, y) == TileType::Empty
-                                        || map.tile(x, y) == TileType::Ladder)
-                                    && !(x == player.x && y == player.y)
-                                    && x < ELEVATOR_SHAFT_X
-                                {
-                                    map.set_tile(x, y, TileType::Dirt);
-                                }
-                            }
-                        }
-                    }
-                    _ => map.set_tile(player.target_x, player.target_y, TileType::Empty),
-                }
+            let dug = player.use_energy(1);
+            if dug {
+                stats.tiles_dug += 1;
+                caved_in = dig(player, map, stats, run, target.x, target.y, spared);
             }
         }
-        TileType::Rock { hardness } => {
-            if player.rock_hammer && player.use_energy(3 << hardness) {
-                map.set_tile(player.target_x, player.target_y, TileType::Empty);
-                player.x = player.target_x;
-                player.y = player.target_y;
+        TileType::Rock { hardness } if player.use_pick => {
+            let broken = player.break_rock(hardness);
+            if broken {
+                stats.rocks_broken += 1;
+                map.set_tile(target.x, target.y, TileType::Empty);
+                pos.arrive(*target);
             }
         }
-        TileType::Treasure { value } => {
-            // Collect the treasure.
-            player.receive_money((1 << value) * TREASURE_BASE_VALUE);
-            map.set_tile(player.target_x, player.target_y, TileType::Empty);
-            player.x = player.target_x;
-            player.y = player.target_y;
+        TileType::Treasure { mineral } => {
+            // Collect the treasure, if there's room for it.
+            let collected = player.inventory.add(Item::Ore(mineral), 1);
+            if collected {
+                stats.found(mineral);
+                map.set_tile(target.x, target.y, TileType::Empty);
+                pos.arrive(*target);
+            }
         }
         _ => {}
     }
 
-    // You can't climb up in thin air.
-    if !player_in_elevator
-        && map.tile(player.x, player.y) == TileType::Empty
-        && player.target_y < player.y
+    // Move towards target, if possible.
+    let moving = !pos.is_at(*target);
+    if moving && !falling && movement::is_passable(map.tile(target.x, target.y)) {
+        lighten_load(player, map, pos.x, pos.y);
+    }
+    let step_energy = if falling { 0 } else { player.step_energy() };
+    if movement::is_passable(map.tile(target.x, target.y))
+        && (!moving || player.use_energy(step_energy))
     {
-        player.target_y = player.y;
+        // Allow the move.
+        if moving && !falling {
+            player.step_wait = player.step_delay();
+        }
+        if moving && falling {
+            player.fall();
+        }
+        pos.arrive(*target);
+    } else {
+        // If we haven't moved there yet, we aren't going to.
+        *target = pos.target();
     }
 
-    // Move towards target, if possible.
-    match map.tile(player.target_x, player.target_y) {
-        TileType::Empty | TileType::Ladder | TileType::Sky => {
-            // Allow the move.
-            player.x = player.target_x;
-            player.y = player.target_y;
+    // Cash in.
+    if pos.x == BANK_X && pos.y == SKY_HEIGHT {
+        player.sell_ore();
+    }
+
+    if player.energy() < energy {
+        stats.energy_spent += energy - player.energy();
+    }
+    if pos.y - GRASS_LEVEL > stats.deepest {
+        stats.reached(pos.y - GRASS_LEVEL);
+    }
+    if pos.y - GRASS_LEVEL > run.deepest {
+        run.reached(pos.y - GRASS_LEVEL);
+    }
+    caved_in
+}
+
+// Digs out the dirt at (x, y), to find whatever is in it. Returns where a
+// cave-in started, if there was one.
+fn dig(
+    player: &mut Player,
+    map: &mut Map,
+    stats: &mut Stats,
+    run: &mut Run,
+    x: i32,
+    y: i32,
+    spared: &[(i32, i32)],
+) -> Option<(i32, i32)> {
+    let ground = &mut run.tile_rng(x, y);
+    let rng = &mut run.rng;
+    match run.difficulty.find(ground.gen_range(0..DIG_ROLL)) {
+        Find::Rock => {
+            map.set_tile(
+                x,
+                y,
+                TileType::Rock {
+                    hardness: ((ground.gen_range(0..50) + y) / 25) as u8,
+                },
+            );
+        }
+        Find::Treasure => {
+            // Treasure, depending on what can be found at this depth.
+            let tile = match Mineral::random_at_depth(y - GRASS_LEVEL, ground) {
+                Some(mineral) => TileType::Treasure { mineral },
+                None => TileType::Empty,
+            };
+            map.set_tile(x, y, tile);
         }
-        _ => {
-            // If we haven't moved there yet, we aren't going to.
-            player.target_x = player.x;
-            player.target_y = player.y;
+        trouble @ (Find::Flood | Find::CaveIn) => {
+            let spotted = player.inventory.count(Item::Lantern) > 0 && rng.gen_bool(0.5);
+            if spotted {
+                // Spotted the trouble in time to dig around it.
+                map.set_tile(x, y, TileType::Empty);
+            } else if trouble == Find::CaveIn && player.inventory.remove(Item::Timber, 1) {
+                // Shored up the tunnel before it came down.
+                map.set_tile(x, y, TileType::Empty);
+            } else if trouble == Find::Flood {
+                flood(map, x, y, spared, rng);
+                stats.floods += 1;
+            } else {
+                cave_in(map, x, y, spared, rng);
+                stats.cave_ins += 1;
+                return Some((x, y));
+            }
+        }
+        Find::Nothing => map.set_tile(x, y, TileType::Empty),
+    }
+    None
+}
+
+// Too tired to carry it all, the player leaves a piece of ore in the tunnel to dig
+// up later, if there's room for it. Otherwise they can't take the step.
+fn lighten_load(player: &mut Player, map: &mut Map, x: i32, y: i32) {
+    if map.tile(x, y) == TileType::Empty && !map.is_shaft(x) {
+        if let Some(mineral) = player.lighten_load() {
+            map.set_tile(x, y, TileType::Treasure { mineral });
         }
     }
+}
+
+pub fn breathe(
+    mut players: Query<(&mut Player, &Position)>,
+    elevators: Query<&Position, With<Elevator>>,
+    map: Res<Map>,
+) {
+    for (mut player, pos) in players.iter_mut() {
+        let player_in_elevator = elevators.iter().any(|car| car == pos);
+
+        let ok = match hazard::air(&map, pos.x, pos.y, player_in_elevator) {
+            Air::Fresh => {
+                player.breathe(20);
+                true
+            }
+            Air::Still => true,
+            Air::Stale => player.use_oxygen(1),
+            Air::Underwater => player.use_oxygen(5),
+        };
+
+        if !ok {
+            // Drowning, or suffocating.
+            player.hurt(SUFFOCATION_DAMAGE);
+        } else if pos.y <= SKY_HEIGHT {
+            // Fresh air does wonders.
+            player.heal(1);
+        }
+    }
+}
+
+// Off to the hospital, leaving the ore behind where the player fell.
+pub fn check_health(
+    mut players: Query<(&mut Player, &mut Position, &mut Target)>,
+    mut map: ResMut<Map>,
+) {
+    for (mut player, mut pos, mut target) in players.iter_mut() {
+        if !player.is_dead() {
+            continue;
+        }
+        let fell = *pos;
+        let ore = player.hospitalize(&mut pos, &mut target);
+        let mut spots = map
+            .flood_fill(fell.x, fell.y, |t| t == TileType::Empty)
+            .into_iter();
+        for (mineral, n) in ore {
+            // Whatever doesn't fit is lost.
+            for (sx, sy) in spots.by_ref().take(n as usize) {
+                map.set_tile(sx, sy, TileType::Treasure { mineral });
+            }
+        }
+    }
+}
+
+// Runs once a day.
+pub fn charge_interest(mut players: Query<&mut Player>) {
+    for mut player in players.iter_mut() {
+        player.loan.charge_interest();
+        if player.is_bankrupt() {
+            player.end_run();
+        }
+    }
+}
+
+// The pumps run on the energy of whoever has enough to spare.
+pub fn run_pumps(mut players: Query<&mut Player>, mut map: ResMut<Map>, mut pumps: ResMut<Pumps>) {
+    pumps.remove_lost(&map);
+    for pump in pumps.iter() {
+        if let Some(cost) = pump.energy_cost(&map) {
+            if let Some(mut player) = players.iter_mut().find(|p| p.energy() >= cost) {
+                if pump.drain(&mut map).is_some() {
+                    player.use_energy(cost);
+                }
+            }
+        }
+    }
+}
+
+pub fn burn_fuses(
+    mut players: Query<(&mut Player, &Position)>,
+    mut map: ResMut<Map>,
+    mut charges: ResMut<Charges>,
+    mut run: ResMut<Run>,
+    time: Res<Time>,
+) {
+    let mut done = charges.tick(time.delta_seconds());
+    while let Some(d) = done.pop() {
+        let area = d.explode(&mut map);
+        for (mut player, pos) in players.iter_mut() {
+            let damage = d.damage(&map, pos.x, pos.y);
+            player.hurt(damage);
+        }
+
+        // Chain reactions.
+        charges.set_off(&area);
+        done.extend(charges.tick(0.0));
+
+        // The blast can shake other things loose.
+        let spared: Vec<_> = players.iter().map(|(_, pos)| (pos.x, pos.y)).collect();
+        match run.rng.gen_range(0..10) {
+            0 => flood(&mut map, d.x, d.y, &spared, &mut run.rng),
+            1 => {
+                cave_in(&mut map, d.x, d.y, &spared, &mut run.rng);
+                for (mut player, pos) in players.iter_mut() {
+                    hurt_by_cave_in(&mut player, pos, d.x, d.y);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Auto-walk is for player one, who has the mouse.
+pub fn auto_walk(
+    mut players: Query<(&PlayerNumber, &Position, &mut Target)>,
+    mut walk: ResMut<AutoWalk>,
+    map: Res<Map>,
+) {
+    if !walk.is_walking() {
+        return;
+    }
+    for (number, pos, mut target) in players.iter_mut() {
+        if *number == PlayerNumber(0) {
+            if let Some((x, y)) = walk.next_step(&map, pos.x, pos.y) {
+                *target = Position::new(x, y).target();
+            }
+        }
+    }
+}
+
+// Nobody near a cave-in escapes the falling rubble.
+fn hurt_by_cave_in(player: &mut Player, pos: &Position, x: i32, y: i32) {
+    if (pos.x - x).abs() <= HAZARD_RANGE && (pos.y - y).abs() <= HAZARD_RANGE {
+        player.hurt(CAVE_IN_DAMAGE);
+    }
+}
+
+// Puts up a ladder from the inventory, or builds one from scratch if there are none.
+pub fn build_ladder(player: &mut Player, map: &mut Map, x: i32, y: i32) -> bool {
+    if x >= ELEVATOR_SHAFT_X || map.is_shaft(x) || map.tile(x, y) != TileType::Empty {
+        return false;
+    }
+    let built = if player.inventory.count(Item::Ladder) > 0 {
+        player.use_energy(1) && player.inventory.remove(Item::Ladder, 1)
+    } else {
+        player.use_energy(5)
+    };
+    if built {
+        map.set_tile(x, y, TileType::Ladder);
+    }
+    built
+}
+
+// Take down the ladder the player is on. They may well fall.
+pub fn take_ladder(player: &mut Player, map: &mut Map, x: i32, y: i32) -> bool {
+    if map.tile(x, y) != TileType::Ladder || !player.use_energy(1) {
+        return false;
+    }
+    player.take_ladder();
+    map.set_tile(x, y, TileType::Empty);
+    true
+}
 
-    // Cash in and recharge.
-    if player.x == MAP_WIDTH - 9 && player.y == SKY_HEIGHT {
-        player.refill_energy();
+// Pull the player out to the elevator and send it home, on credit if need be.
+pub fn rescue(
+    player: &mut Player,
+    pos: &mut Position,
+    target: &mut Target,
+    elev: &mut Elevator,
+    car: &Position,
+) {
+    if !player.pay_on_credit(RESCUE_COST) {
+        // Bankrupt.
+        player.end_run();
+        return;
     }
+    *pos = *car;
+    *target = pos.target();
+    elev.send(0);
 }
@@ -15,12 +15,24 @@
 #![warn(clippy::all)]
 
 use crate::constants::*;
-use crate::model::elevator::Elevator;
+use crate::model::animation::{Animation, Pose};
+use crate::model::dynamite::Charges;
+use crate::model::elevator::{Breakdown, Elevator};
 use crate::model::map::{Map, TileType};
-use crate::model::player::Player;
+use crate::model::mineral::Mineral;
+use crate::model::player::{Player, PlayerNumber, MAX_PLAYERS};
+use crate::model::position::Position;
+use crate::systems::input::HoveredTile;
 use bevy::{math::ivec3, prelude::*};
 use bevy_simple_tilemap::prelude::*;
 
+// The tile sprites, for things that aren't drawn on the tile map.
+pub struct Tileset(Handle<TextureAtlas>);
+
+// Sprites are drawn over the tile map, with the players in front of the elevator.
+const ELEVATOR_Z: f32 = 1.0;
+const PLAYER_Z: f32 = 2.0;
+
 pub fn setup(
     asset_server: Res<AssetServer>,
     mut commands: Commands,
@@ -30,6 +42,7 @@ pub fn setup(
     let texture_handle = asset_server.load("64x64_tileset.png");
     let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(64.0, 64.0), 10, 4);
     let texture_atlas_handle = texture_atlases.add(texture_atlas);
+    commands.insert_resource(Tileset(texture_atlas_handle.clone()));
 
     let cam = OrthographicCameraBundle::new_2d();
     commands.spawn_bundle(cam);
@@ -51,116 +64,205 @@ pub fn setup(
     commands.insert_resource(map);
 }
 
-pub fn show_player(player: Res<Player>, mut query: Query<&mut TileMap>) {
+// Player two is tinted, to tell the miners apart.
+const PLAYER_COLORS: [Color; MAX_PLAYERS] = [Color::WHITE, Color::rgb(0.6, 0.8, 1.0)];
+
+// Gives anything newly animated a sprite.
+pub fn add_sprites(
+    mut commands: Commands,
+    tileset: Res<Tileset>,
+    query: Query<(Entity, &Animation, Option<&Elevator>), Without<TextureAtlasSprite>>,
+) {
+    for (entity, anim, elev) in query.iter() {
+        let (index, z) = match elev {
+            Some(_) => (SpriteIndex::Elevator, ELEVATOR_Z),
+            None => (SpriteIndex::Person, PLAYER_Z),
+        };
+        commands.entity(entity).insert_bundle(SpriteSheetBundle {
+            sprite: TextureAtlasSprite::new(index as usize),
+            texture_atlas: tileset.0.clone(),
+            transform: Transform::from_xyz(64.0 * anim.x, -64.0 * anim.y, z),
+            ..Default::default()
+        });
+    }
+}
+
+// Slides the sprites along after their positions on the grid.
+pub fn animate(
+    mut query: Query<(&mut Animation, &Position, &mut Transform)>,
+    map: Res<Map>,
+    time: Res<Time>,
+) {
+    for (mut anim, pos, mut tf) in query.iter_mut() {
+        // Hanging on to a ladder, or just standing around.
+        let rest = match map.tile(pos.x, pos.y) {
+            TileType::Ladder => Pose::Climb,
+            _ => Pose::Stand,
+        };
+        anim.update(*pos, rest, time.delta_seconds());
+        tf.translation.x = 64.0 * anim.x;
+        tf.translation.y = -64.0 * anim.y;
+    }
+}
+
+pub fn show_player(
+    mut players: Query<(&PlayerNumber, &Animation, &mut TextureAtlasSprite), With<Player>>,
+) {
+    for (&PlayerNumber(number), anim, mut sprite) in players.iter_mut() {
+        let (index, flip) = anim.sprite();
+        sprite.index = index as usize;
+        sprite.flip_x = flip;
+        sprite.color = PLAYER_COLORS[number];
+    }
+}
+
+pub fn show_dynamite(charges: Res<Charges>, mut query: Query<&mut TileMap>) {
     for mut tm in query.iter_mut() {
-        tm.clear_layer(PLAYER_LAYER);
+        tm.clear_layer(DYNAMITE_LAYER);
 
-        tm.set_tile(
-            ivec3(player.x, -player.y, PLAYER_LAYER),
-            Some(Tile {
-                sprite_index: SpriteIndex::Person as u32,
-                ..Default::default()
-            }),
-        );
+        for d in charges.iter() {
+            tm.set_tile(
+                ivec3(d.x, -d.y, DYNAMITE_LAYER),
+                Some(Tile {
+                    sprite_index: SpriteIndex::Dynamite as u32,
+                    ..Default::default()
+                }),
+            );
+        }
     }
 }
 
-pub fn show_elevator(elev: Res<Elevator>, mut query: Query<&mut TileMap>) {
+pub fn show_cursor(hovered: Res<HoveredTile>, mut query: Query<&mut TileMap>) {
+    if !hovered.is_changed() {
+        return;
+    }
     for mut tm in query.iter_mut() {
-        tm.clear_layer(ELEVATOR_LAYER);
+        tm.clear_layer(CURSOR_LAYER);
 
-        // Elevator body.
-        tm.set_tile(
-            ivec3(
-                ELEVATOR_SHAFT_X,
-                1 - GRASS_LEVEL - elev.depth() as i32,
-                ELEVATOR_LAYER,
-            ),
-            Some(Tile {
-                sprite_index: SpriteIndex::Elevator as u32,
-                ..Default::default()
-            }),
-        );
-        for i in 0..elev.depth() {
+        if let Some((x, y)) = hovered.0 {
             tm.set_tile(
-                ivec3(ELEVATOR_SHAFT_X, 1 - GRASS_LEVEL - i as i32, ELEVATOR_LAYER),
+                ivec3(x, -y, CURSOR_LAYER),
                 Some(Tile {
-                    sprite_index: SpriteIndex::ElevatorCable as u32,
+                    sprite_index: SpriteIndex::Cursor as u32,
                     ..Default::default()
                 }),
             );
         }
-        tm.set_tile(
-            ivec3(ELEVATOR_SHAFT_X, 2 - GRASS_LEVEL, ELEVATOR_LAYER),
-            Some(Tile {
-                sprite_index: SpriteIndex::ElevatorHook as u32,
-                ..Default::default()
-            }),
-        );
-        tm.set_tile(
-            ivec3(ELEVATOR_SHAFT_X + 1, 2 - GRASS_LEVEL, ELEVATOR_LAYER),
-            Some(Tile {
-                sprite_index: SpriteIndex::ElevatorTowerTop as u32,
-                ..Default::default()
-            }),
-        );
-        tm.set_tile(
-            ivec3(ELEVATOR_SHAFT_X + 1, 1 - GRASS_LEVEL, ELEVATOR_LAYER),
-            Some(Tile {
-                sprite_index: SpriteIndex::ElevatorTowerBottom as u32,
-                ..Default::default()
-            }),
-        );
     }
 }
 
-pub fn update_tilemap(player: Res<Player>, map: Res<Map>, mut query: Query<&mut TileMap>) {
+// The car is a sprite of its own, so this is the cable down to it, the tower and
+// the call buttons, lit up while they're waiting for the car. A worn car goes rusty,
+// and a broken one red, with no cable if it snapped.
+pub fn show_elevator(
+    mut elevators: Query<(
+        &Elevator,
+        &Position,
+        &Animation,
+        Option<&mut TextureAtlasSprite>,
+    )>,
+    mut query: Query<&mut TileMap>,
+) {
     for mut tm in query.iter_mut() {
-        if tm.chunks.is_empty() {
-            for x in 0..MAP_WIDTH {
-                for y in 0..MAP_HEIGHT {
-                    set_tile(&mut tm, x, -y, map.tile(x, y));
-                }
+        tm.clear_layer(ELEVATOR_LAYER);
+
+        for (elev, car, anim, sprite) in elevators.iter_mut() {
+            let cable = match elev.breakdown() {
+                Some(Breakdown::Snapped) => 0,
+                _ => (anim.y - SKY_HEIGHT as f32).ceil() as i32,
+            };
+            show_shaft(&mut tm, car.x, cable);
+            if let Some(mut sprite) = sprite {
+                let worn = f32::max(elev.cable_wear(), elev.motor_wear());
+                sprite.color = match elev.breakdown() {
+                    Some(_) => Color::rgb(1.0, 0.3, 0.3),
+                    None => Color::rgb(1.0, 1.0 - 0.4 * worn, 1.0 - 0.7 * worn),
+                };
             }
-        } else {
-            for x in player.x - 10..=player.x + 10 {
-                for y in player.y - 10..=player.y + 10 {
-                    set_tile(&mut tm, x, -y, map.tile(x, y));
-                }
+            for depth in elev.buttons() {
+                tm.set_tile(
+                    ivec3(car.x - 1, -SKY_HEIGHT - depth, ELEVATOR_LAYER),
+                    Some(Tile {
+                        sprite_index: SpriteIndex::CallButton as u32,
+                        color: if elev.is_called(depth) {
+                            Color::YELLOW
+                        } else {
+                            Color::WHITE
+                        },
+                        ..Default::default()
+                    }),
+                );
             }
         }
+    }
+}
 
-        // For now, just draw the bank over top.
-        // TODO: Make the bank a real entity.
-        tm.set_tile(
-            ivec3(MAP_WIDTH - 10, 1 - SKY_HEIGHT, 0),
-            Some(Tile {
-                sprite_index: SpriteIndex::BankTopLeft as u32,
-                ..Default::default()
-            }),
-        );
-        tm.set_tile(
-            ivec3(MAP_WIDTH - 9, 1 - SKY_HEIGHT, 0),
-            Some(Tile {
-                sprite_index: SpriteIndex::BankTopRight as u32,
-                ..Default::default()
-            }),
-        );
+// The cable, as far down as the given depth, and the tower, for the shaft in column x.
+fn show_shaft(tm: &mut TileMap, x: i32, depth: i32) {
+    for i in 0..depth {
         tm.set_tile(
-            ivec3(MAP_WIDTH - 10, -SKY_HEIGHT, 0),
+            ivec3(x, 1 - GRASS_LEVEL - i, ELEVATOR_LAYER),
             Some(Tile {
-                sprite_index: SpriteIndex::BankBottomLeft as u32,
-                ..Default::default()
-            }),
-        );
-        tm.set_tile(
-            ivec3(MAP_WIDTH - 9, -SKY_HEIGHT, 0),
-            Some(Tile {
-                sprite_index: SpriteIndex::BankBottomRight as u32,
+                sprite_index: SpriteIndex::ElevatorCable as u32,
                 ..Default::default()
             }),
         );
     }
+    tm.set_tile(
+        ivec3(x, 2 - GRASS_LEVEL, ELEVATOR_LAYER),
+        Some(Tile {
+            sprite_index: SpriteIndex::ElevatorHook as u32,
+            ..Default::default()
+        }),
+    );
+    tm.set_tile(
+        ivec3(x + 1, 2 - GRASS_LEVEL, ELEVATOR_LAYER),
+        Some(Tile {
+            sprite_index: SpriteIndex::ElevatorTowerTop as u32,
+            ..Default::default()
+        }),
+    );
+    tm.set_tile(
+        ivec3(x + 1, 1 - GRASS_LEVEL, ELEVATOR_LAYER),
+        Some(Tile {
+            sprite_index: SpriteIndex::ElevatorTowerBottom as u32,
+            ..Default::default()
+        }),
+    );
+}
+
+pub fn update_tilemap(mut map: ResMut<Map>, mut query: Query<&mut TileMap>) {
+    let dirty = map.take_dirty();
+    if dirty.is_empty() {
+        return;
+    }
+    for mut tm in query.iter_mut() {
+        for &(x, y) in dirty.iter() {
+            // For now, just draw the bank over top.
+            // TODO: Make the bank a real entity.
+            match bank_sprite(x, y) {
+                Some(si) => tm.set_tile(
+                    ivec3(x, -y, 0),
+                    Some(Tile {
+                        sprite_index: si as u32,
+                        ..Default::default()
+                    }),
+                ),
+                None => set_tile(&mut tm, x, -y, map.tile(x, y)),
+            }
+        }
+    }
+}
+
+fn bank_sprite(x: i32, y: i32) -> Option<SpriteIndex> {
+    // The bank door is at BANK_X, on the surface.
+    match (x - BANK_X, y - SKY_HEIGHT) {
+        (-1, -1) => Some(SpriteIndex::BankTopLeft),
+        (0, -1) => Some(SpriteIndex::BankTopRight),
+        (-1, 0) => Some(SpriteIndex::BankBottomLeft),
+        (0, 0) => Some(SpriteIndex::BankBottomRight),
+        _ => None,
+    }
 }
 
 fn set_tile(tm: &mut TileMap, x: i32, y: i32, t: TileType) {
@@ -173,12 +275,18 @@ fn set_tile(tm: &mut TileMap, x: i32, y: i32, t: TileType) {
         TileType::Grass => SpriteIndex::Grass as u32,
         TileType::Dirt => SpriteIndex::Dirt as u32,
         TileType::Ladder => SpriteIndex::Ladder as u32,
+        TileType::Pump => SpriteIndex::Pump as u32,
         TileType::Rock { hardness } => {
             (SpriteIndex::Stone0 as u8 + core::cmp::min(hardness, 3)) as u32
         }
-        TileType::Treasure { value } => {
-            (SpriteIndex::Treasure0 as u8 + core::cmp::min(value, 2)) as u32
-        }
+        TileType::Treasure { mineral } => match mineral {
+            Mineral::Coal => SpriteIndex::Coal as u32,
+            Mineral::Copper => SpriteIndex::Copper as u32,
+            Mineral::Silver => SpriteIndex::Silver as u32,
+            Mineral::Gold => SpriteIndex::Gold as u32,
+            Mineral::Ruby => SpriteIndex::Ruby as u32,
+            Mineral::Diamond => SpriteIndex::Diamond as u32,
+        },
         TileType::Water => SpriteIndex::Water as u32,
         _ => SpriteIndex::Border as u32,
     };
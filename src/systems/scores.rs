@@ -0,0 +1,96 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::animation::Animation;
+use crate::model::dynamite::Charges;
+use crate::model::elevator::Elevator;
+use crate::model::key_repeat::MoveBuffer;
+use crate::model::map::Map;
+use crate::model::path::AutoWalk;
+use crate::model::player::{is_team_done, start_position, team_net_worth, Player};
+use crate::model::position::{Position, Target};
+use crate::model::pump::Pumps;
+use crate::model::run::Run;
+use crate::model::score::HighScores;
+use crate::systems::elevator::spawn_elevator;
+use bevy::prelude::*;
+
+// Whether the high scores get saved. A file that couldn't be read is left alone,
+// rather than overwritten with just the new scores.
+pub struct SaveScores(bool);
+
+pub fn setup(mut commands: Commands) {
+    let (scores, save) = match HighScores::load(SCORES_FILE) {
+        Ok(scores) => (scores, true),
+        Err(e) => {
+            warn!("Starting with no high scores, which won't be saved: {}", e);
+            (HighScores::default(), false)
+        }
+    };
+    commands.insert_resource(scores);
+    commands.insert_resource(SaveScores(save));
+}
+
+pub fn count_time(mut run: ResMut<Run>, time: Res<Time>) {
+    run.seconds += time.delta_seconds();
+}
+
+// Records the score for the team, and starts a new game in a fresh mine.
+pub fn end_run(
+    mut commands: Commands,
+    mut players: Query<(
+        &mut Player,
+        &mut Position,
+        &mut Target,
+        &mut Animation,
+        &mut MoveBuffer,
+    )>,
+    elevators: Query<Entity, With<Elevator>>,
+    mut run: ResMut<Run>,
+    mut scores: ResMut<HighScores>,
+    save: Res<SaveScores>,
+) {
+    if !is_team_done(players.iter().map(|(player, ..)| player)) {
+        return;
+    }
+    let net_worth = team_net_worth(players.iter().map(|(player, ..)| player));
+    scores.add(run.score(net_worth));
+    if save.0 {
+        if let Err(e) = scores.save(SCORES_FILE) {
+            warn!("Couldn't save high scores: {}", e);
+        }
+    }
+    *run = run.next();
+
+    // Nothing is left of the old mine, including any shafts that were built.
+    commands.insert_resource(Map::new(MAP_WIDTH as usize, MAP_HEIGHT as usize));
+    commands.insert_resource(Pumps::default());
+    commands.insert_resource(Charges::default());
+    commands.insert_resource(AutoWalk::default());
+    for entity in elevators.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_elevator(&mut commands, ELEVATOR_SHAFT_X, MAX_ELEVATOR_DEPTH, 0);
+
+    for (mut player, mut pos, mut target, mut anim, mut buffer) in players.iter_mut() {
+        *player = Player::new();
+        *pos = start_position();
+        *target = pos.target();
+        *anim = Animation::new(*pos);
+        *buffer = MoveBuffer::default();
+    }
+}
@@ -0,0 +1,48 @@
+//  Copyright 2022 Google LLC
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      https://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+#![warn(clippy::all)]
+
+use crate::constants::*;
+use crate::model::stats::Stats;
+use bevy::prelude::*;
+
+// Whether the statistics get saved. A file that couldn't be read is left alone,
+// rather than overwritten with fresh statistics.
+pub struct SaveStats(bool);
+
+pub fn setup(mut commands: Commands) {
+    let (stats, save) = match Stats::load(STATS_FILE) {
+        Ok(stats) => (stats, true),
+        Err(e) => {
+            warn!(
+                "Starting with fresh statistics, which won't be saved: {}",
+                e
+            );
+            (Stats::default(), false)
+        }
+    };
+    commands.insert_resource(stats);
+    commands.insert_resource(SaveStats(save));
+}
+
+// Runs every few seconds, so at most a few seconds of play go uncounted if the
+// game is closed.
+pub fn save_stats(stats: Res<Stats>, save: Res<SaveStats>) {
+    if stats.is_changed() && save.0 {
+        if let Err(e) = stats.save(STATS_FILE) {
+            warn!("Couldn't save statistics: {}", e);
+        }
+    }
+}
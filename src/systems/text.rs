@@ -14,15 +14,55 @@
 
 #![warn(clippy::all)]
 
-use crate::model::player::Player;
+use crate::constants::*;
+use crate::model::bank::{INTEREST_PERCENT, LOAN_STEP};
+use crate::model::elevator::{Breakdown, Elevator};
+use crate::model::energy::{self, BUDGET_STEP, ENERGY_STEP};
+use crate::model::inventory::Item;
+use crate::model::player::{Player, PlayerNumber, MAX_ENERGY, MAX_HEALTH, MAX_PLAYERS};
+use crate::model::position::Position;
+use crate::model::score::HighScores;
+use crate::model::shop::Shop;
+use crate::model::stats::Stats;
+use crate::systems::input::Screen;
 use bevy::prelude::*;
 
+type PlayerQuery<'w, 's> = Query<'w, 's, (&'static PlayerNumber, &'static Player)>;
+
 #[derive(Component)]
 pub struct MoneyText;
 
 #[derive(Component)]
 pub struct EnergyText;
 
+#[derive(Component)]
+pub struct OxygenText;
+
+#[derive(Component)]
+pub struct PumpText;
+
+#[derive(Component)]
+pub struct LoadText;
+
+#[derive(Component)]
+pub struct PickText;
+
+#[derive(Component)]
+pub struct ShopText;
+
+#[derive(Component)]
+pub struct ScreenText;
+
+// Each player has their own bars, shown once they are in the game.
+#[derive(Component)]
+pub struct PlayerBars(usize);
+
+#[derive(Component)]
+pub struct HealthBar(usize);
+
+#[derive(Component)]
+pub struct EnergyBar(usize);
+
 pub fn setup(asset_server: Res<AssetServer>, mut commands: Commands) {
     commands.spawn_bundle(UiCameraBundle::default());
     commands
@@ -86,16 +126,469 @@ pub fn setup(asset_server: Res<AssetServer>, mut commands: Commands) {
             ..Default::default()
         })
         .insert(EnergyText);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: "  Oxygen: ".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                            font_size: 30.0,
+                            color: Color::CYAN,
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(OxygenText);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: "  Pumps: ".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                            font_size: 30.0,
+                            color: Color::SILVER,
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(PumpText);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: "  Load: ".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                            font_size: 30.0,
+                            color: Color::BEIGE,
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(LoadText);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                align_self: AlignSelf::FlexEnd,
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: "  Pick: ".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                            font_size: 30.0,
+                            color: Color::SILVER,
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(PickText);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(50.0),
+                    left: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![TextSection {
+                    value: "".to_string(),
+                    style: TextStyle {
+                        font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ShopText);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(50.0),
+                    left: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text {
+                sections: vec![TextSection {
+                    value: "".to_string(),
+                    style: TextStyle {
+                        font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                        font_size: 24.0,
+                        color: Color::WHITE,
+                    },
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ScreenText);
+
+    // Health and energy bars, in the top right corner.
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for idx in 0..MAX_PLAYERS {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            margin: Rect {
+                                bottom: Val::Px(10.0),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        color: Color::NONE.into(),
+                        ..Default::default()
+                    })
+                    .insert(PlayerBars(idx))
+                    .with_children(|bars| {
+                        spawn_bar(bars, Color::RED, HealthBar(idx));
+                        spawn_bar(bars, Color::ORANGE, EnergyBar(idx));
+                    });
+            }
+        });
+}
+
+fn spawn_bar(parent: &mut ChildBuilder, color: Color, marker: impl Component) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(200.0), Val::Px(16.0)),
+                margin: Rect {
+                    bottom: Val::Px(6.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            color: Color::DARK_GRAY.into(),
+            ..Default::default()
+        })
+        .with_children(|bar| {
+            bar.spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    ..Default::default()
+                },
+                color: color.into(),
+                ..Default::default()
+            })
+            .insert(marker);
+        });
+}
+
+// Players, in order.
+fn in_order<'a>(players: impl Iterator<Item = (&'a PlayerNumber, &'a Player)>) -> Vec<&'a Player> {
+    let mut players: Vec<_> = players.collect();
+    players.sort_by_key(|(number, _)| number.0);
+    players.into_iter().map(|(_, player)| player).collect()
 }
 
-pub fn update_money(player: Res<Player>, mut query: Query<&mut Text, With<MoneyText>>) {
+// One value per player, for the status line.
+fn per_player(players: &PlayerQuery, f: impl Fn(&Player) -> String) -> String {
+    in_order(players.iter())
+        .into_iter()
+        .map(f)
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+pub fn update_money(players: PlayerQuery, mut query: Query<&mut Text, With<MoneyText>>) {
     for mut text in query.iter_mut() {
-        text.sections[1].value = format!("{}", player.money());
+        text.sections[1].value = per_player(&players, |player| match player.loan.debt() {
+            0 => format!("{}", player.money()),
+            debt => format!("{} (owe {})", player.money(), debt),
+        });
     }
 }
 
-pub fn update_energy(player: Res<Player>, mut query: Query<&mut Text, With<EnergyText>>) {
+pub fn update_energy(players: PlayerQuery, mut query: Query<&mut Text, With<EnergyText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[1].value = per_player(&players, |player| format!("{}", player.energy()));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_bars(
+    players: PlayerQuery,
+    mut bars: Query<(&PlayerBars, &mut Style), (Without<HealthBar>, Without<EnergyBar>)>,
+    mut health: Query<(&HealthBar, &mut Style), (Without<PlayerBars>, Without<EnergyBar>)>,
+    mut energy: Query<(&EnergyBar, &mut Style), (Without<PlayerBars>, Without<HealthBar>)>,
+) {
+    let players = in_order(players.iter());
+    for (PlayerBars(idx), mut style) in bars.iter_mut() {
+        style.display = if *idx < players.len() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    for (HealthBar(idx), mut style) in health.iter_mut() {
+        if let Some(player) = players.get(*idx) {
+            style.size.width = Val::Percent(100.0 * player.health() as f32 / MAX_HEALTH as f32);
+        }
+    }
+    for (EnergyBar(idx), mut style) in energy.iter_mut() {
+        if let Some(player) = players.get(*idx) {
+            style.size.width = Val::Percent(100.0 * player.energy() as f32 / MAX_ENERGY as f32);
+        }
+    }
+}
+
+pub fn update_oxygen(players: PlayerQuery, mut query: Query<&mut Text, With<OxygenText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[1].value = per_player(&players, |player| format!("{}", player.oxygen()));
+    }
+}
+
+pub fn update_pumps(players: PlayerQuery, mut query: Query<&mut Text, With<PumpText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[1].value = per_player(&players, |player| {
+            format!("{}", player.inventory.count(Item::Pump))
+        });
+    }
+}
+
+pub fn update_load(players: PlayerQuery, mut query: Query<&mut Text, With<LoadText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[1].value = per_player(&players, |player| {
+            format!(
+                "{}/{}",
+                player.inventory.weight(),
+                player.inventory.max_weight()
+            )
+        });
+    }
+}
+
+pub fn update_pick(players: PlayerQuery, mut query: Query<&mut Text, With<PickText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[1].value = per_player(&players, |player| player.pick.name().to_string());
+    }
+}
+
+pub fn update_shop(
+    players: Query<(&Player, &Position)>,
+    elevators: Query<(&Elevator, &Position), Without<Player>>,
+    shop: Res<Shop>,
+    screen: Res<Screen>,
+    mut query: Query<&mut Text, With<ShopText>>,
+) {
+    let mut value = String::new();
+    let at_bank = players
+        .iter()
+        .find(|(_, pos)| pos.x == BANK_X && pos.y == SKY_HEIGHT);
+    if let (Screen::Game, Some((player, _))) = (*screen, at_bank) {
+        value += "Welcome to the bank!\n";
+        for (idx, e) in shop.entries().iter().enumerate() {
+            value += &format!(
+                "{}{}. {} x{} for ${} - {} (have {})\n",
+                if elevators
+                    .iter()
+                    .any(|(elev, _)| shop.can_buy(player, elev, idx))
+                {
+                    " "
+                } else {
+                    "x"
+                },
+                // The tenth is on the 0 key.
+                (idx + 1) % 10,
+                e.item.name(),
+                e.quantity,
+                e.price,
+                e.description,
+                player.inventory.count(e.item)
+            );
+        }
+        let n = i32::min(ENERGY_STEP, MAX_ENERGY - player.energy());
+        value += &format!(
+            "\nEnergy: F to fill up (${}), E for {} more (${}), M for ${} worth.\n\
+             Bulk discounts:",
+            energy::cost(MAX_ENERGY - player.energy()),
+            n,
+            energy::cost(n),
+            BUDGET_STEP
+        );
+        for (start, price) in energy::TIERS {
+            value += &format!(" ${} each from {},", price, start + 1);
+        }
+        value.pop();
+        value += "\n";
+        value += &format!(
+            "\nYou owe ${} of ${} credit, at {}% interest a day.\n\
+             Press + to borrow ${}, or - to pay back ${}.\n\
+             \nPress Q to retire and record your score.\n",
+            player.loan.debt(),
+            player.loan.limit(),
+            INTEREST_PERCENT,
+            LOAN_STEP,
+            LOAN_STEP
+        );
+    } else if let (Screen::Game, Some((elev, _))) = (
+        *screen,
+        elevators.iter().find(|(_, car)| {
+            players
+                .iter()
+                .any(|(_, pos)| pos.x == car.x - 1 && pos.y == SKY_HEIGHT)
+        }),
+    ) {
+        // At the top of a shaft, the state of its elevator.
+        value += &format!(
+            "Elevator: cable {}% worn, motor {}% worn.\n",
+            (elev.cable_wear() * 100.0).round(),
+            (elev.motor_wear() * 100.0).round()
+        );
+        value += match elev.breakdown() {
+            Some(Breakdown::Stuck) => "The motor has failed, and the car is stuck.\n",
+            Some(Breakdown::Snapped) => "The cable has snapped!\n",
+            None => "",
+        };
+        if elev.repair_cost() > 0 {
+            value += &format!("Press W to repair it for ${}.\n", elev.repair_cost());
+        }
+    }
+    for mut text in query.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+pub fn update_screen(
+    stats: Res<Stats>,
+    scores: Res<HighScores>,
+    screen: Res<Screen>,
+    mut query: Query<&mut Text, With<ScreenText>>,
+) {
+    if !stats.is_changed() && !scores.is_changed() && !screen.is_changed() {
+        return;
+    }
+    let mut value = String::new();
+    match *screen {
+        Screen::Game => {}
+        Screen::Stats => {
+            value += "Lifetime statistics (Tab for high scores)\n\n";
+            for (label, n) in stats.lines() {
+                value += &format!("{:<30}{:>8}\n", label, n);
+            }
+        }
+        Screen::Scores(by) => {
+            value += &format!(
+                "High scores, by {} (S to sort differently, Tab to go back)\n\n",
+                by.name()
+            );
+            value += &format!(
+                "{:<16}{:>8}{:>7}{:>8}  {:<8}{}\n",
+                "Name", "Money", "Depth", "Time", "Level", "Seed"
+            );
+            for s in scores.sorted(by).iter().take(20) {
+                value += &format!(
+                    "{:<16}{:>8}{:>7}{:>5}:{:02}  {:<8}{}\n",
+                    s.name,
+                    s.money,
+                    s.depth,
+                    s.seconds / 60,
+                    s.seconds % 60,
+                    s.difficulty.name(),
+                    s.seed
+                );
+            }
+        }
+    }
     for mut text in query.iter_mut() {
-        text.sections[1].value = format!("{}", player.energy());
+        text.sections[0].value = value.clone();
     }
 }